@@ -0,0 +1,58 @@
+//! Measures the cost of preparing a frame of sprites for drawing. This doesn't touch the graphics
+//! device, so it measures only the CPU side.
+
+#![feature(test)]
+
+extern crate test;
+extern crate ggez;
+extern crate omn_labs;
+
+use ggez::graphics::{Color, DrawParam};
+use omn_labs::assets::ImageHandle;
+use omn_labs::components::Origin;
+use omn_labs::render;
+use omn_labs::sprites::Region;
+use omn_labs::systems::DrawCommand;
+
+const SPRITES: usize = 5000;
+const ATLAS_SIZE: (f32, f32) = (512., 512.);
+
+/// Builds a frame of commands as the `Renderer` would emit them, with sprites taken from two
+/// atlases.
+fn frame() -> Vec<DrawCommand> {
+    let mut commands: Vec<DrawCommand> = (0..SPRITES)
+        .map(|i| {
+            DrawCommand::DrawTransformed {
+                image: ImageHandle::from_raw((i * 2 / SPRITES) as u32, 0),
+                frame: 0,
+                src: Some(Region {
+                    x: (i % 16) as i32 * 32,
+                    y: (i / 16 % 16) as i32 * 32,
+                    width: 32,
+                    height: 32,
+                }),
+                origin: Origin::Normalized(0.5, 0.5),
+                color: Color::new(1., 1., 1., 1.),
+                x: (i % 100) as f32 * 8.,
+                y: (i / 100) as f32 * 8.,
+                rot: 0.,
+                sx: 1.,
+                sy: 1.,
+            }
+        })
+        .collect();
+    commands.push(DrawCommand::Flush);
+    commands
+}
+
+#[bench]
+fn draw_params_per_sprite(b: &mut test::Bencher) {
+    let commands = frame();
+    b.iter(|| {
+        let params: Vec<DrawParam> = commands
+            .iter()
+            .filter_map(|cmd| render::draw_param(cmd, ATLAS_SIZE.0, ATLAS_SIZE.1))
+            .collect();
+        params.len()
+    });
+}
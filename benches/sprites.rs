@@ -3,37 +3,41 @@
 extern crate test;
 extern crate omn_labs;
 
-use omn_labs::sprites::{ClipStore, SpriteSheetData, PlayMode};
+use omn_labs::sprites::{ClipStore, Delta, SpriteSheetData, PlayMode};
 
 
 #[bench]
 fn create_clip_store(b: &mut test::Bencher) {
-    let sheet = SpriteSheetData::from_file("resources/numbers/numbers-matrix-tags.array.json");
+    let sheet = SpriteSheetData::from_file("resources/numbers/numbers-matrix-tags.array.json")
+        .unwrap();
     b.iter(|| ClipStore::new(&sheet));
 }
 
 #[bench]
 fn create_clip_instance(b: &mut test::Bencher) {
-    let sheet = SpriteSheetData::from_file("resources/numbers/numbers-matrix-tags.array.json");
+    let sheet = SpriteSheetData::from_file("resources/numbers/numbers-matrix-tags.array.json")
+        .unwrap();
     let clips = ClipStore::new(&sheet);
     b.iter(|| clips.create("Alpha", PlayMode::Loop).unwrap());
 }
 
 #[bench]
 fn clip_update(b: &mut test::Bencher) {
-    let sheet = SpriteSheetData::from_file("resources/numbers/numbers-matrix-tags.array.json");
+    let sheet = SpriteSheetData::from_file("resources/numbers/numbers-matrix-tags.array.json")
+        .unwrap();
     let clips = ClipStore::new(&sheet);
     let mut clip = clips.create("Alpha", PlayMode::Loop).unwrap();
-    b.iter(|| clip.update(800.));
+    b.iter(|| clip.update(Delta::from_millis(800.)));
 }
 
 #[bench]
 fn clip_get_cell(b: &mut test::Bencher) {
-    let sheet = SpriteSheetData::from_file("resources/numbers/numbers-matrix-tags.array.json");
+    let sheet = SpriteSheetData::from_file("resources/numbers/numbers-matrix-tags.array.json")
+        .unwrap();
     let clips = ClipStore::new(&sheet);
     let mut clip = clips.create("Alpha", PlayMode::Loop).unwrap();
     b.iter(|| {
-        clip.update(800.);
+        clip.update(Delta::from_millis(800.));
         clip.get_cell()
     });
 }
@@ -7,6 +7,9 @@ extern crate rand;
 extern crate omn_labs;
 
 use omn_labs::components;
+use omn_labs::render;
+use omn_labs::resources::{AnimationEvents, Camera, Time};
+use omn_labs::timing::GameLoop;
 use omn_labs::systems;
 use omn_labs::assets;
 
@@ -16,13 +19,13 @@ use ggez::{GameResult, Context};
 use ggez::graphics;
 use ggez::timer;
 use specs::{Join, WriteStorage, DispatcherBuilder, World, Dispatcher, Fetch};
-use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::mpsc::Sender;
 use std::time::Duration;
-use assets::AssetBundle;
+use assets::{loaders, AssetBundle, ImageHandle};
 
 use systems::DrawCommand;
 
-pub struct DeltaTime(f32);
+const WINDOW_SIZE: u32 = 300;
 
 #[derive(Clone)]
 pub struct Spinner {
@@ -30,11 +33,11 @@ pub struct Spinner {
 }
 
 impl<'a> specs::System<'a> for Spinner {
-    type SystemData = (WriteStorage<'a, components::Body>, Fetch<'a, DeltaTime>);
+    type SystemData = (WriteStorage<'a, components::Body>, Fetch<'a, Time>);
     fn run(&mut self, data: Self::SystemData) {
 
-        let (mut body, delta) = data;
-        let dt = delta.0;
+        let (mut body, time) = data;
+        let dt = time.delta.as_secs();
         // update entities
         for b in (&mut body).join() {
             b.rotation += dt * self.factor * rand::random::<f32>();
@@ -47,66 +50,79 @@ impl<'a> specs::System<'a> for Spinner {
 pub struct Game<'a, 'b> {
     pub dispatcher: Dispatcher<'a, 'b>,
     pub world: World,
+    pub game_loop: GameLoop,
 }
 
 
 impl<'a, 'b> Game<'a, 'b> {
-    pub fn new(render_tx: Sender<DrawCommand>) -> Self {
+    pub fn new(render_tx: Sender<DrawCommand>, logo: ImageHandle) -> Self {
         // The world is in charge of component storage, and as such contains all the game state.
         let mut world = World::new();
         world.register::<components::Sprited>();
         world.register::<components::Body>();
-        world.add_resource(DeltaTime(0.));
+        world.register::<components::Animated>();
+        world.register::<components::Parent>();
+        world.register::<components::GlobalTransform>();
+        world.add_resource(Time::default());
+        world.add_resource(AnimationEvents::default());
+        world.add_resource(Camera::new(WINDOW_SIZE as f32, WINDOW_SIZE as f32));
 
         // entities are created by combining various components via the world
-        world
+        let spinner = world
             .create_entity()
-            .with(components::Sprited {
-                path: "/rust_128x128x1.png".to_string(),
+            .with(components::Sprited::new(logo))
+            .with(components::Body {
+                origin: components::Origin::Normalized(0.5, 0.5),
+                ..components::Body::new(150., 150.)
             })
+            .build();
+
+        // a smaller logo which orbits the first as it spins
+        world
+            .create_entity()
+            .with(components::Sprited::new(logo))
             .with(components::Body {
-                x: 150.,
-                y: 150.,
-                scale_x: 1.,
-                scale_y: 1.,
-                rotation: 0.,
+                scale_x: 0.25,
+                scale_y: 0.25,
+                origin: components::Origin::Normalized(0.5, 0.5),
+                ..components::Body::new(80., 0.)
             })
+            .with(components::Parent { entity: spinner })
             .build();
 
         let dispatcher = DispatcherBuilder::new()
             .add(Spinner { factor: 25. }, "spinner", &[])
+            .add(systems::Animator, "animator", &[])
+            .add(
+                systems::TransformPropagation,
+                "transform_propagation",
+                &["spinner"],
+            )
             .add(
-                systems::Renderer {
-                    tx: render_tx.clone(),
-                },
+                systems::Renderer::new(render_tx.clone()),
                 "renderer",
-                &[],
+                &["transform_propagation", "animator"],
             )
             .build();
 
         Game {
             dispatcher: dispatcher,
             world: world,
+            game_loop: GameLoop::default(),
         }
     }
 
-    pub fn tick(&mut self, dt: f32) -> () {
-
-        {
-            let mut delta = self.world.write_resource::<DeltaTime>();
-            *delta = DeltaTime(dt);
-        }
-
-        self.dispatcher.dispatch(&mut self.world.res);
-
+    pub fn tick(&mut self, dt: Duration) -> () {
+        self.game_loop.update(&mut self.world, &mut self.dispatcher, dt);
+        // Nothing reacts to animation events yet, but they pile up until drained.
+        self.world.write_resource::<AnimationEvents>().drain();
     }
 }
 
 
 struct MainState<'a, 'b> {
     ecs: Game<'a, 'b>,
-    render_rx: Receiver<DrawCommand>,
-    assets: AssetBundle,
+    executor: render::Executor,
 }
 
 
@@ -115,12 +131,15 @@ impl<'a, 'b> MainState<'a, 'b> {
 
         ctx.print_resource_stats();
 
-        let (tx, rx) = channel::<DrawCommand>();
+        let manifest = loaders::load_manifest(ctx, "/spinner-manifest.json")?;
+        let mut assets = AssetBundle::from_manifest(ctx, &manifest)?;
+        let logo = assets.acquire_image(ctx, &manifest.images[0])?;
+
+        let (executor, tx) = render::Executor::channel(assets);
 
         let s = MainState {
-            render_rx: rx,
-            ecs: Game::new(tx),
-            assets: AssetBundle::new(ctx, &vec!["/rust_128x128x1.png"]),
+            ecs: Game::new(tx, logo),
+            executor: executor,
         };
         Ok(s)
     }
@@ -129,25 +148,14 @@ impl<'a, 'b> MainState<'a, 'b> {
 
 impl<'a, 'b> event::EventHandler for MainState<'a, 'b> {
     fn update(&mut self, _ctx: &mut Context, _dt: Duration) -> GameResult<()> {
-        let delta_secs = _dt.subsec_nanos() as f32 / 1e9;
-        self.ecs.tick(delta_secs);
+        self.ecs.tick(_dt);
         Ok(())
     }
 
     fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
         graphics::clear(ctx);
 
-        for cmd in self.render_rx.try_iter() {
-            match cmd {
-                DrawCommand::DrawTransformed {
-                    path, x, y, rot, ..
-                } => {
-                    let image = self.assets.get_image(&path);
-                    graphics::draw(ctx, image, graphics::Point::new(x, y), rot)?;
-                }
-                DrawCommand::Flush => {}
-            }
-        }
+        self.executor.draw(ctx)?;
 
         graphics::present(ctx);
         println!("Approx FPS: {}", timer::get_fps(ctx));
@@ -159,8 +167,8 @@ impl<'a, 'b> event::EventHandler for MainState<'a, 'b> {
 pub fn main() {
 
     let mut conf = conf::Conf::new();
-    conf.window_height = 300;
-    conf.window_width = 300;
+    conf.window_height = WINDOW_SIZE;
+    conf.window_width = WINDOW_SIZE;
     conf.window_title = "Omn Labs RS".to_string();
 
     println!("Starting with default config: {:#?}", conf);
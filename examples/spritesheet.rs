@@ -8,6 +8,10 @@ extern crate ggez;
 extern crate rand;
 
 use omn_labs::assets;
+use omn_labs::render;
+use omn_labs::Delta;
+#[cfg(feature = "hot-reload")]
+use omn_labs::hotreload::{Change, HotReloader};
 
 use std::time::Duration;
 use std::rc::Rc;
@@ -18,44 +22,81 @@ use ggez::event;
 use ggez::{GameResult, Context};
 use ggez::graphics;
 use ggez::graphics::DrawParam;
-use assets::AssetBundle;
+use assets::{loaders, AssetBundle};
 
 
+const SHEET_PATH: &str = "resources/numbers/numbers-matrix-tags.array.json";
+
 struct MainState {
     clip: Option<RefCell<AnimationClip>>,
     sheet: Rc<SpriteSheetData>,
     clips: Rc<ClipStore>,
     assets: AssetBundle,
+    #[cfg(feature = "hot-reload")]
+    reloader: HotReloader,
 }
 
 
 impl MainState {
     fn new(ctx: &mut Context) -> GameResult<MainState> {
         ctx.print_resource_stats();
-        let sheet = Rc::new(SpriteSheetData::from_file(
-            "resources/numbers/numbers-matrix-tags.array.json",
-        ));
+        let sheet = Rc::new(SpriteSheetData::from_file(SHEET_PATH)?);
         let clips = Rc::new(ClipStore::new(sheet.as_ref()));
+        let manifest = loaders::load_manifest(ctx, "/spritesheet-manifest.json")?;
         let mut s = MainState {
             clips: clips,
             clip: None,
             sheet: sheet.clone(),
-            assets: AssetBundle::new(ctx, &vec!["/numbers-matrix.png"]),
+            assets: AssetBundle::from_manifest(ctx, &manifest)?,
+            #[cfg(feature = "hot-reload")]
+            reloader: HotReloader::new(concat!(env!("CARGO_MANIFEST_DIR"), "/resources"))?,
         };
+        #[cfg(feature = "hot-reload")]
+        {
+            s.reloader.watch_bundle(&s.assets)?;
+            s.reloader.watch_sheet(SHEET_PATH)?;
+        }
         s.clip = Some(RefCell::new(
             s.clips.create("Alpha", PlayMode::Loop).unwrap(),
         ));
         Ok(s)
     }
+
+    /// Picks up any changes made to the atlas or sheet data since the last frame.
+    #[cfg(feature = "hot-reload")]
+    fn reload(&mut self, ctx: &mut Context) {
+        for change in self.reloader.poll_bundle(&self.assets) {
+            match change {
+                Ok(Change::Image(path)) => {
+                    if let Err(e) = self.assets.reload_image(ctx, &path) {
+                        println!("Reload failed: {}", e);
+                    }
+                }
+                Ok(Change::Sheet(path)) => match SpriteSheetData::from_file(&path) {
+                    Ok(sheet) => {
+                        self.sheet = Rc::new(sheet);
+                        self.clips = Rc::new(ClipStore::new(self.sheet.as_ref()));
+                        if let Some(ref clip) = self.clip {
+                            self.clips.refresh(&mut clip.borrow_mut());
+                        }
+                    }
+                    Err(e) => println!("Reload failed: {}", e),
+                },
+                Err(e) => println!("{}", e),
+            }
+        }
+    }
 }
 
 
 impl event::EventHandler for MainState {
     fn update(&mut self, _ctx: &mut Context, _dt: Duration) -> GameResult<()> {
 
+        #[cfg(feature = "hot-reload")]
+        self.reload(_ctx);
+
         if let Some(ref clip) = self.clip {
-            let delta_millis = _dt.subsec_nanos() as f32 / 1e6;
-            clip.borrow_mut().update(delta_millis)
+            clip.borrow_mut().update(Delta::from(_dt));
         }
         Ok(())
     }
@@ -64,19 +105,12 @@ impl event::EventHandler for MainState {
         graphics::clear(ctx);
 
         if let Some(ref clip) = self.clip {
-            let atlas = { self.assets.get_image("/numbers-matrix.png") };
-            let w = atlas.width() as f32;
-            let h = atlas.height() as f32;
+            let atlas = self.assets.get_image(ctx, "/numbers-matrix.png")?;
 
             let idx = clip.borrow().get_cell().unwrap();
             let cell = &self.sheet.frames[idx];
             let param = DrawParam {
-                src: graphics::Rect::new(
-                    cell.bbox.x as f32 / w,
-                    cell.bbox.y as f32 / h,
-                    cell.bbox.width as f32 / w,
-                    cell.bbox.height as f32 / h,
-                ),
+                src: render::src_rect(&cell.bbox, atlas.width() as f32, atlas.height() as f32),
                 dest: graphics::Point::new(160., 120.),
                 scale: graphics::Point::new(1.5, 1.5),
                 ..Default::default()
@@ -0,0 +1,291 @@
+//! The `background` module loads assets on worker threads, so a game can keep drawing (a loading
+//! screen, say) while large atlases are decoded.
+//!
+//! Only the parts of loading which don't need the ggez `Context` happen on the workers: reading
+//! files, decoding image bytes and parsing Aseprite json. Decoded images are uploaded to the
+//! graphics device on the main thread, a few at a time, by `BackgroundLoader::update()`.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! extern crate ggez;
+//! extern crate omn_labs;
+//!
+//! use ggez::{Context, GameResult};
+//! use omn_labs::assets::AssetBundle;
+//! use omn_labs::assets::background::BackgroundLoader;
+//!
+//! // Called once per frame until loading is finished.
+//! fn draw_loading_screen(
+//!     ctx: &mut Context,
+//!     loader: &mut BackgroundLoader,
+//!     assets: &mut AssetBundle,
+//! ) -> GameResult<()> {
+//!     // Upload at most 2 decoded assets this frame, to keep the frame rate up.
+//!     let progress = loader.update(ctx, assets, 2);
+//!     println!(
+//!         "Loading {:.0}% ({})",
+//!         progress.fraction() * 100.,
+//!         progress.current.unwrap_or_default()
+//!     );
+//!     Ok(())
+//! }
+//! # fn main() {}
+//! ```
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+
+use ggez::Context;
+use ggez::graphics::Image;
+use image;
+
+use errors::{Error, Result};
+use sprites::{ClipStore, SpriteSheetData};
+use super::{resource_path, AssetBundle};
+use super::loaders::asset_error;
+use super::manifest::Group;
+
+/// `Progress` is a snapshot of how far along a `BackgroundLoader` is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Progress {
+    /// The number of assets which have finished loading (successfully or not).
+    pub loaded: usize,
+    pub total: usize,
+    /// The path of the asset most recently finished, if any.
+    pub current: Option<String>,
+}
+
+impl Progress {
+    /// How much of the work is done, from 0 to 1.
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            1.
+        } else {
+            self.loaded as f32 / self.total as f32
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.loaded >= self.total
+    }
+}
+
+enum Job {
+    Image(String),
+    Sheet(String),
+}
+
+/// The result of a job, ready to be handed to ggez.
+enum Decoded {
+    Image {
+        width: u16,
+        height: u16,
+        rgba: Vec<u8>,
+    },
+    Sheet(SpriteSheetData),
+}
+
+/// `BackgroundLoader` decodes images and parses sheet data on worker threads, then adds the
+/// results to an `AssetBundle` as they are polled.
+///
+/// Since the workers can't use ggez's filesystem, paths are resolved against `resource_root`
+/// (see `resource_path()`).
+pub struct BackgroundLoader {
+    rx: Receiver<(String, Result<Decoded>)>,
+    total: usize,
+    loaded: usize,
+    current: Option<String>,
+    errors: Vec<Error>,
+}
+
+impl BackgroundLoader {
+    /// Starts loading each image and sheet in `group` using `workers` threads.
+    pub fn new<P: AsRef<Path>>(resource_root: P, group: &Group, workers: usize) -> Self {
+        let mut jobs: VecDeque<Job> = group
+            .image_paths()
+            .into_iter()
+            .map(|x| Job::Image(x.to_string()))
+            .collect();
+        jobs.extend(group.sheet_paths().into_iter().map(
+            |x| Job::Sheet(x.to_string()),
+        ));
+
+        let total = jobs.len();
+        let jobs = Arc::new(Mutex::new(jobs));
+        let (tx, rx) = channel();
+        for _ in 0..workers.max(1) {
+            let root = resource_root.as_ref().to_path_buf();
+            let jobs = jobs.clone();
+            let tx = tx.clone();
+            thread::spawn(move || work(&root, &jobs, &tx));
+        }
+
+        BackgroundLoader {
+            rx: rx,
+            total: total,
+            loaded: 0,
+            current: None,
+            errors: vec![],
+        }
+    }
+
+    /// Adds up to `max_uploads` finished assets to `bundle`, then reports progress. Failures are
+    /// collected, to be reported by `BackgroundLoader::finish()`.
+    ///
+    /// Sheets are added to the bundle both as `SpriteSheetData` and as a `ClipStore`.
+    pub fn update(
+        &mut self,
+        ctx: &mut Context,
+        bundle: &mut AssetBundle,
+        max_uploads: usize,
+    ) -> Progress {
+        for (path, result) in self.rx.try_iter().take(max_uploads) {
+            let uploaded = result.and_then(|decoded| match decoded {
+                Decoded::Image {
+                    width,
+                    height,
+                    rgba,
+                } => {
+                    Image::from_rgba8(ctx, width, height, &rgba)
+                        .map(|image| bundle.insert(&path, image))
+                        .map_err(|e| asset_error(&path, e))
+                }
+                Decoded::Sheet(sheet) => {
+                    bundle.insert(&path, ClipStore::new(&sheet));
+                    bundle.insert(&path, sheet);
+                    Ok(())
+                }
+            });
+            if let Err(e) = uploaded {
+                self.errors.push(e);
+            }
+            self.loaded += 1;
+            self.current = Some(path);
+        }
+        self.progress()
+    }
+
+    pub fn progress(&self) -> Progress {
+        Progress {
+            loaded: self.loaded,
+            total: self.total,
+            current: self.current.clone(),
+        }
+    }
+
+    /// Consumes the loader, reporting every asset which failed to load. Assets still in flight
+    /// are abandoned.
+    pub fn finish(self) -> Result<()> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Multiple(self.errors))
+        }
+    }
+}
+
+/// Runs jobs from the shared queue until it's empty, or the loader has been dropped.
+fn work(root: &Path, jobs: &Mutex<VecDeque<Job>>, tx: &Sender<(String, Result<Decoded>)>) {
+    loop {
+        let job = match jobs.lock().expect("job queue lock poisoned").pop_front() {
+            Some(job) => job,
+            None => return,
+        };
+        let result = match job {
+            Job::Image(path) => {
+                let decoded = decode_image(&resource_path(root, &path));
+                (path, decoded)
+            }
+            Job::Sheet(path) => {
+                let parsed = SpriteSheetData::from_file(resource_path(root, &path));
+                (path, parsed.map(Decoded::Sheet))
+            }
+        };
+        if tx.send(result).is_err() {
+            return;
+        }
+    }
+}
+
+fn decode_image(file: &Path) -> Result<Decoded> {
+    let mut bytes = vec![];
+    File::open(file)
+        .and_then(|mut f| f.read_to_end(&mut bytes))
+        .map_err(|e| {
+            Error::Io {
+                path: Some(file.to_path_buf()),
+                cause: e,
+            }
+        })?;
+    let rgba = image::load_from_memory(&bytes)
+        .map_err(|e| {
+            Error::Decode {
+                path: file.to_path_buf(),
+                cause: e,
+            }
+        })?
+        .to_rgba();
+    let (width, height) = rgba.dimensions();
+    if width > u16::max_value() as u32 || height > u16::max_value() as u32 {
+        return Err(Error::Decode {
+            path: file.to_path_buf(),
+            cause: image::ImageError::DimensionError,
+        });
+    }
+    Ok(Decoded::Image {
+        width: width as u16,
+        height: height as u16,
+        rgba: rgba.into_raw(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decode_image() {
+        match decode_image(Path::new("resources/rust_128x128x1.png")).unwrap() {
+            Decoded::Image {
+                width,
+                height,
+                rgba,
+            } => {
+                assert_eq!((width, height), (128, 128));
+                assert_eq!(rgba.len(), 128 * 128 * 4);
+            }
+            Decoded::Sheet(_) => panic!("expected an image"),
+        }
+    }
+
+    #[test]
+    fn test_workers_report_every_job() {
+        let group = Group {
+            images: vec!["/rust_128x128x1.png".to_string(), "/missing.png".to_string()],
+            sheets: vec![],
+        };
+        let loader = BackgroundLoader::new("resources", &group, 2);
+        assert_eq!(loader.progress().total, 2);
+
+        let mut results: Vec<(String, bool)> = loader
+            .rx
+            .iter()
+            .take(2)
+            .map(|(path, result)| (path, result.is_ok()))
+            .collect();
+        results.sort();
+        assert_eq!(
+            results,
+            vec![
+                ("/missing.png".to_string(), false),
+                ("/rust_128x128x1.png".to_string(), true),
+            ]
+        );
+    }
+}
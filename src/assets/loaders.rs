@@ -0,0 +1,81 @@
+//! The `loaders` module contains the `Loader` trait used by `AssetBundle` to read each type of
+//! asset, along with loaders for the asset types supported out of the box.
+
+use std::path::{Path, PathBuf};
+use ggez::{Context, GameError};
+use ggez::audio;
+use ggez::graphics::{Font, Image};
+use errors::{Error, Result};
+use sprites::{ClipStore, SpriteSheetData};
+use super::manifest::Manifest;
+
+/// The point size used for fonts loaded by the default font loader.
+pub const DEFAULT_FONT_SIZE: u32 = 16;
+
+/// A `Loader` knows how to produce an asset of type `T` from a path relative to the application's
+/// resource root.
+///
+/// Any `Fn(&mut Context, &str) -> Result<T>` is a `Loader<T>`, so closures can be registered
+/// directly with `AssetBundle::register_loader()`.
+pub trait Loader<T> {
+    fn load(&self, ctx: &mut Context, path: &str) -> Result<T>;
+}
+
+impl<T, F> Loader<T> for F
+where
+    F: Fn(&mut Context, &str) -> Result<T>,
+{
+    fn load(&self, ctx: &mut Context, path: &str) -> Result<T> {
+        self(ctx, path)
+    }
+}
+
+/// Loads fonts at a fixed point size.
+pub struct FontLoader {
+    pub size: u32,
+}
+
+impl Default for FontLoader {
+    fn default() -> Self {
+        FontLoader { size: DEFAULT_FONT_SIZE }
+    }
+}
+
+impl Loader<Font> for FontLoader {
+    fn load(&self, ctx: &mut Context, path: &str) -> Result<Font> {
+        Font::new(ctx, path, self.size).map_err(|e| asset_error(path, e))
+    }
+}
+
+/// Wraps a ggez error with the path of the asset which failed to load.
+pub fn asset_error(path: &str, cause: GameError) -> Error {
+    Error::Asset {
+        path: PathBuf::from(path),
+        cause: cause,
+    }
+}
+
+pub fn load_image(ctx: &mut Context, path: &str) -> Result<Image> {
+    Image::new(ctx, path).map_err(|e| asset_error(path, e))
+}
+
+pub fn load_sound(ctx: &mut Context, path: &str) -> Result<audio::Source> {
+    audio::Source::new(ctx, path).map_err(|e| asset_error(path, e))
+}
+
+/// Reads Aseprite export data through ggez's filesystem, so sheet paths are resolved the same way
+/// as image paths.
+pub fn load_sheet(ctx: &mut Context, path: &str) -> Result<SpriteSheetData> {
+    let file = ctx.filesystem.open(path).map_err(|e| asset_error(path, e))?;
+    SpriteSheetData::from_reader(file, Some(Path::new(path)))
+}
+
+pub fn load_clips(ctx: &mut Context, path: &str) -> Result<ClipStore> {
+    load_sheet(ctx, path).map(|sheet| ClipStore::new(&sheet))
+}
+
+/// Reads a manifest through ggez's filesystem.
+pub fn load_manifest(ctx: &mut Context, path: &str) -> Result<Manifest> {
+    let file = ctx.filesystem.open(path).map_err(|e| asset_error(path, e))?;
+    Manifest::from_reader(file, Some(Path::new(path)))
+}
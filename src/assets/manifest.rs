@@ -0,0 +1,154 @@
+//! The `manifest` module describes the contents of an `AssetBundle` as data, so assets can be
+//! added or rearranged without recompiling.
+//!
+//! A manifest is a JSON document listing images and Aseprite sheets (each paired with its atlas
+//! image), plus any number of named groups of the same. Paths are relative to the application's
+//! resource root.
+//!
+//! ```json
+//! {
+//!   "images": ["/rust_128x128x1.png"],
+//!   "sheets": [
+//!     { "data": "/numbers/numbers-matrix-tags.array.json", "image": "/numbers-matrix.png" }
+//!   ],
+//!   "groups": {
+//!     "title-screen": { "images": ["/title.png"] }
+//!   }
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use serde_json;
+use errors::{Error, Result};
+
+/// An Aseprite sheet: the exported json data, and the atlas image its frames refer to.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct SheetEntry {
+    pub data: String,
+    pub image: String,
+}
+
+/// A named set of assets which can be loaded and unloaded together.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+pub struct Group {
+    #[serde(default)]
+    pub images: Vec<String>,
+    #[serde(default)]
+    pub sheets: Vec<SheetEntry>,
+}
+
+/// `Manifest` lists the assets to load into an `AssetBundle`.
+///
+/// The top level images and sheets are loaded by `AssetBundle::from_manifest()`, while groups
+/// are only loaded when requested via `AssetBundle::load_group()`.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+pub struct Manifest {
+    #[serde(default)]
+    pub images: Vec<String>,
+    #[serde(default)]
+    pub sheets: Vec<SheetEntry>,
+    #[serde(default)]
+    pub groups: HashMap<String, Group>,
+}
+
+impl Group {
+    /// Every image in the group, including the atlas images of its sheets.
+    pub fn image_paths(&self) -> Vec<&str> {
+        self.images
+            .iter()
+            .map(|x| x.as_str())
+            .chain(self.sheets.iter().map(|x| x.image.as_str()))
+            .collect()
+    }
+
+    pub fn sheet_paths(&self) -> Vec<&str> {
+        self.sheets.iter().map(|x| x.data.as_str()).collect()
+    }
+}
+
+impl Manifest {
+    pub fn parse_str(json: &str) -> Result<Manifest> {
+        serde_json::from_str(json).map_err(|e| Error::from_json(e, None))
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Manifest> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|e| {
+            Error::Io {
+                path: Some(path.to_path_buf()),
+                cause: e,
+            }
+        })?;
+        Self::from_reader(file, Some(path))
+    }
+
+    /// Reads a manifest from any `Read`, such as a file opened via ggez's filesystem. `path` is
+    /// only used to annotate errors.
+    pub fn from_reader<R: Read>(reader: R, path: Option<&Path>) -> Result<Manifest> {
+        serde_json::from_reader(reader).map_err(|e| Error::from_json(e, path))
+    }
+
+    /// The top level assets, which are not part of any group.
+    pub fn ungrouped(&self) -> Group {
+        Group {
+            images: self.images.clone(),
+            sheets: self.sheets.clone(),
+        }
+    }
+
+    pub fn group(&self, name: &str) -> Result<&Group> {
+        self.groups.get(name).ok_or_else(
+            || Error::UnknownGroup(name.to_string()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let manifest = Manifest::parse_str(
+            r#"{
+          "images": ["/rust_128x128x1.png"],
+          "sheets": [
+            { "data": "/numbers/numbers-matrix-tags.array.json", "image": "/numbers-matrix.png" }
+          ],
+          "groups": {
+            "title-screen": { "images": ["/title.png"] }
+          }
+        }"#,
+        ).unwrap();
+
+        let top = manifest.ungrouped();
+        assert_eq!(
+            top.image_paths(),
+            vec!["/rust_128x128x1.png", "/numbers-matrix.png"]
+        );
+        assert_eq!(
+            top.sheet_paths(),
+            vec!["/numbers/numbers-matrix-tags.array.json"]
+        );
+
+        let title = manifest.group("title-screen").unwrap();
+        assert_eq!(title.image_paths(), vec!["/title.png"]);
+        assert!(title.sheets.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_group() {
+        let manifest = Manifest::parse_str("{}").unwrap();
+        match manifest.group("level-1") {
+            Err(Error::UnknownGroup(name)) => assert_eq!(name, "level-1"),
+            other => panic!("expected an unknown group error, got {:?}", other),
+        }
+        assert_eq!(
+            manifest.group("level-1").unwrap_err().to_string(),
+            "no asset group named `level-1`"
+        );
+    }
+}
@@ -0,0 +1,755 @@
+//! The `assets` module contains `AssetBundle`, a loader and cache for the files a game needs at
+//! runtime.
+
+use ggez::{Context, GameResult};
+use ggez::audio;
+use ggez::graphics::{Font, Image};
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::mem;
+use std::path::{Path, PathBuf};
+use errors::{Error, Result};
+use sprites::{ClipStore, SpriteSheetData};
+
+pub mod background;
+pub mod loaders;
+pub mod manifest;
+
+use self::loaders::{FontLoader, Loader};
+use self::manifest::{Group, Manifest};
+
+/// `AssetBundle` acts as a file loader and cache for assets of any type, keyed by path.
+///
+/// Each type of asset is read by a `Loader` registered with the bundle. Loaders for
+/// `ggez::graphics::Image`, `ggez::graphics::Font`, `ggez::audio::Source`, `SpriteSheetData` and
+/// `ClipStore` are registered by default, and can be replaced (or loaders for new types added) via
+/// `AssetBundle::register_loader()`.
+///
+/// # Examples
+// Note that this will not run because the resource system will not be able to locate the image
+// file during the test run.
+/// ```no_run
+///
+/// extern crate ggez;
+/// extern crate omn_labs;
+///
+/// use ggez::conf;
+/// use ggez::Context;
+/// use ggez::graphics::Image;
+/// use omn_labs::assets::AssetBundle;
+/// use omn_labs::sprites::ClipStore;
+///
+/// fn main() {
+///     let mut conf = conf::Conf::new();
+///     conf.window_height = 300;
+///     conf.window_width = 300;
+///     conf.window_title = "Omn Labs RS".to_string();
+///
+///     let ctx = &mut Context::load_from_conf("Omn Labs", "omnlabs", conf).unwrap();
+///
+///     let mut assets = AssetBundle::new(ctx, &[]).unwrap();
+///
+///     // Paths are relative to the application's resource root.
+///     // The first time an image is requested, it is loaded and cached.
+///     let width = assets.get_image(ctx, "/rust_128x128x1.png").unwrap().width();
+///
+///     // Subsequent requests for the same path return a reference to the cached image, and
+///     // don't need a `Context`.
+///     assert_eq!(assets.try_get_image("/rust_128x128x1.png").unwrap().width(), width);
+///
+///     // Paths which were never loaded can be checked for without loading them.
+///     assert!(assets.try_get_image("/typo.png").is_none());
+///
+///     // Other types of asset are cached the same way.
+///     let clips = assets
+///         .get::<ClipStore>(ctx, "/numbers/numbers-matrix-tags.array.json")
+///         .unwrap();
+///
+///     // Handles avoid path lookups altogether, and are counted so unused assets can be freed.
+///     let logo = assets.acquire_image(ctx, "/rust_128x128x1.png").unwrap();
+///     assert_eq!(assets.resolve(logo).unwrap().width(), width);
+///     assets.release(logo);
+///
+///     // Level transitions can release everything in one go, or load the next set up-front.
+///     assets.clear();
+///     assets.preload::<Image>(ctx, &["/numbers-matrix.png"]).unwrap();
+/// }
+/// ```
+pub struct AssetBundle {
+    stores: HashMap<TypeId, Box<dyn AnyStore>>,
+}
+
+/// Maps a resource path (as given to `AssetBundle`) to a location on disk, given the directory
+/// acting as the resource root.
+pub fn resource_path<P: AsRef<Path>>(resource_root: P, path: &str) -> PathBuf {
+    resource_root.as_ref().join(path.trim_start_matches('/'))
+}
+
+/// Generates a small magenta and black checkerboard, suitable for use as a fallback image
+/// which stands out on screen when an asset is missing.
+pub fn missing_texture(ctx: &mut Context) -> GameResult<Image> {
+    const SIZE: u16 = 16;
+    const CHECK: u16 = 4;
+    let mut rgba = Vec::with_capacity(SIZE as usize * SIZE as usize * 4);
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            if (x / CHECK + y / CHECK) % 2 == 0 {
+                rgba.extend_from_slice(&[255, 0, 255, 255]);
+            } else {
+                rgba.extend_from_slice(&[0, 0, 0, 255]);
+            }
+        }
+    }
+    Image::from_rgba8(ctx, SIZE, SIZE, &rgba)
+}
+
+/// `Handle` is a small, copyable reference to an asset held by an `AssetBundle`.
+///
+/// Handles are handed out by `AssetBundle::acquire()`, and resolving one (via
+/// `AssetBundle::resolve()`) is an index into a `Vec` rather than a path lookup, which makes them
+/// suitable for storing in components and passing along with draw commands.
+///
+/// The bundle counts how many times each asset has been acquired. Once every acquisition has been
+/// matched by a call to `AssetBundle::release()`, the asset is freed, unless it was also loaded by
+/// `AssetBundle::get()`, `preload()` or `insert()`, which keep it until it's unloaded. Copying a
+/// handle does not affect the count.
+pub struct Handle<T> {
+    index: u32,
+    generation: u32,
+    // `fn() -> T` keeps the handle `Send` and `Sync` regardless of `T`, since no `T` is held.
+    marker: PhantomData<fn() -> T>,
+}
+
+/// A `Handle` to a `ggez::graphics::Image`.
+pub type ImageHandle = Handle<Image>;
+
+impl<T> Handle<T> {
+    /// Builds a handle from its parts. Handles are normally obtained from
+    /// `AssetBundle::acquire()`; one built by hand will only resolve if it happens to match a
+    /// live asset, which makes this mostly useful for tests and benchmarks.
+    pub fn from_raw(index: u32, generation: u32) -> Self {
+        Handle {
+            index: index,
+            generation: generation,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index && self.generation == other.generation
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+        self.generation.hash(state);
+    }
+}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Handle({}v{})", self.index, self.generation)
+    }
+}
+
+/// A cached asset, and the bookkeeping needed to free it.
+struct Entry<T> {
+    path: String,
+    /// `None` when the asset failed to load and is being served by the fallback.
+    asset: Option<T>,
+    refs: usize,
+    /// Whether the asset stays cached when no handles to it remain. Only assets which were
+    /// loaded solely by acquiring handles to them are freed by the reference count.
+    pinned: bool,
+}
+
+/// A position in a `Store` which may or may not be holding an asset. The generation is bumped
+/// each time the slot is freed, so stale handles to it can be detected.
+struct Slot<T> {
+    generation: u32,
+    entry: Option<Entry<T>>,
+}
+
+/// The cache for a single type of asset.
+struct Store<T> {
+    slots: Vec<Slot<T>>,
+    /// Maps paths to indexes in `slots`.
+    index: HashMap<String, usize>,
+    /// Indexes of empty slots, available for reuse.
+    free: Vec<usize>,
+    fallback: Option<T>,
+    /// Load failures which were covered up by the fallback, since the last
+    /// `AssetBundle::take_warnings()`.
+    warnings: Vec<Error>,
+    loader: Box<dyn Loader<T>>,
+}
+
+/// Operations which can be performed on a `Store` without knowing its asset type.
+trait AnyStore {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn clear(&mut self);
+    fn take_warnings(&mut self) -> Vec<Error>;
+}
+
+impl<T: 'static> AnyStore for Store<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn clear(&mut self) {
+        let indexes: Vec<usize> = self.index.values().cloned().collect();
+        for idx in indexes {
+            self.free_slot(idx);
+        }
+    }
+
+    fn take_warnings(&mut self) -> Vec<Error> {
+        mem::replace(&mut self.warnings, vec![])
+    }
+}
+
+impl<T> Store<T> {
+    fn new(loader: Box<dyn Loader<T>>) -> Self {
+        Store {
+            slots: vec![],
+            index: HashMap::new(),
+            free: vec![],
+            fallback: None,
+            warnings: vec![],
+            loader: loader,
+        }
+    }
+
+    /// Returns the slot index for `path`, loading the asset first if it's not already cached.
+    /// When `pin` is set, the asset is kept even once no handles to it remain.
+    fn load(&mut self, ctx: &mut Context, path: &str, pin: bool) -> Result<usize> {
+        if let Some(&idx) = self.index.get(path) {
+            if pin {
+                self.pin(idx);
+            }
+            return Ok(idx);
+        }
+        let asset = match self.loader.load(ctx, path) {
+            Ok(asset) => Some(asset),
+            Err(e) => {
+                if self.fallback.is_none() {
+                    return Err(e);
+                }
+                self.warnings.push(e);
+                None
+            }
+        };
+        Ok(self.insert(path, asset, pin))
+    }
+
+    fn insert(&mut self, path: &str, asset: Option<T>, pinned: bool) -> usize {
+        let entry = Entry {
+            path: path.to_string(),
+            asset: asset,
+            refs: 0,
+            pinned: pinned,
+        };
+        let idx = match self.free.pop() {
+            Some(idx) => {
+                self.slots[idx].entry = Some(entry);
+                idx
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    entry: Some(entry),
+                });
+                self.slots.len() - 1
+            }
+        };
+        self.index.insert(path.to_string(), idx);
+        idx
+    }
+
+    fn pin(&mut self, idx: usize) {
+        if let Some(entry) = self.slots[idx].entry.as_mut() {
+            entry.pinned = true;
+        }
+    }
+
+    fn free_slot(&mut self, idx: usize) -> Option<T> {
+        let entry = match self.slots[idx].entry.take() {
+            Some(entry) => entry,
+            None => return None,
+        };
+        self.slots[idx].generation = self.slots[idx].generation.wrapping_add(1);
+        self.index.remove(&entry.path);
+        self.free.push(idx);
+        entry.asset
+    }
+
+    /// The asset in the slot at `idx`, or the fallback if the asset failed to load.
+    fn asset(&self, idx: usize) -> Option<&T> {
+        self.slots[idx]
+            .entry
+            .as_ref()
+            .and_then(|entry| entry.asset.as_ref().or(self.fallback.as_ref()))
+    }
+
+    fn get(&mut self, ctx: &mut Context, path: &str) -> Result<&T> {
+        let idx = self.load(ctx, path, true)?;
+        Ok(self.asset(idx).expect(
+            "asset should either be cached or substituted by the fallback",
+        ))
+    }
+
+    fn acquire(&mut self, ctx: &mut Context, path: &str) -> Result<Handle<T>> {
+        let idx = self.load(ctx, path, false)?;
+        Ok(self.retain(idx))
+    }
+
+    /// Adds one to the reference count of the occupied slot at `idx`, returning a handle to it.
+    fn retain(&mut self, idx: usize) -> Handle<T> {
+        let slot = &mut self.slots[idx];
+        slot.entry.as_mut().expect("slot is occupied").refs += 1;
+        Handle {
+            index: idx as u32,
+            generation: slot.generation,
+            marker: PhantomData,
+        }
+    }
+
+    /// The slot index for `handle`, if the asset it refers to is still cached.
+    fn slot_for(&self, handle: Handle<T>) -> Option<usize> {
+        let idx = handle.index as usize;
+        match self.slots.get(idx) {
+            Some(slot) if slot.generation == handle.generation && slot.entry.is_some() => Some(idx),
+            _ => None,
+        }
+    }
+
+    fn release(&mut self, handle: Handle<T>) {
+        if let Some(idx) = self.slot_for(handle) {
+            let unused = {
+                let entry = self.slots[idx].entry.as_mut().expect("slot is occupied");
+                entry.refs = entry.refs.saturating_sub(1);
+                entry.refs == 0 && !entry.pinned
+            };
+            if unused {
+                self.free_slot(idx);
+            }
+        }
+    }
+
+    fn preload(&mut self, ctx: &mut Context, paths: &[&str], errors: &mut Vec<Error>) {
+        for path in paths {
+            let cached = match self.index.get(*path) {
+                Some(&idx) => self.slots[idx].entry.as_ref().map_or(false, |e| e.asset.is_some()),
+                None => false,
+            };
+            if !cached {
+                if let Err(e) = self.reload(ctx, path) {
+                    errors.push(e);
+                    continue;
+                }
+            }
+            let idx = self.index[*path];
+            self.pin(idx);
+        }
+    }
+
+    fn reload(&mut self, ctx: &mut Context, path: &str) -> Result<()> {
+        let asset = self.loader.load(ctx, path)?;
+        self.put(path, asset);
+        Ok(())
+    }
+
+    /// Replaces the asset for `path` in place, so existing handles to it see the new asset. An
+    /// asset not already cached is pinned, as there are no handles to it.
+    fn put(&mut self, path: &str, asset: T) -> usize {
+        match self.index.get(path) {
+            Some(&idx) => {
+                self.slots[idx].entry.as_mut().expect("indexed slot is occupied").asset =
+                    Some(asset);
+                idx
+            }
+            None => self.insert(path, Some(asset), true),
+        }
+    }
+
+    fn unload(&mut self, path: &str) -> Option<T> {
+        match self.index.get(path) {
+            Some(&idx) => self.free_slot(idx),
+            None => None,
+        }
+    }
+
+    fn paths(&self) -> Vec<&str> {
+        self.index.keys().map(|k| k.as_str()).collect()
+    }
+}
+
+impl AssetBundle {
+    /// Creates a bundle with the default loaders registered, but nothing loaded.
+    pub fn empty() -> Self {
+        let mut bundle = AssetBundle { stores: HashMap::new() };
+        bundle.register_loader::<Image, _>(loaders::load_image);
+        bundle.register_loader::<Font, _>(FontLoader::default());
+        bundle.register_loader::<audio::Source, _>(loaders::load_sound);
+        bundle.register_loader::<SpriteSheetData, _>(loaders::load_sheet);
+        bundle.register_loader::<ClipStore, _>(loaders::load_clips);
+        bundle
+    }
+
+    /// Loads each image in `image_sources`. If any fail to load, the error will list every
+    /// path that failed, not just the first.
+    ///
+    /// Assets not listed in `image_sources` can still be loaded later, on demand.
+    pub fn new(ctx: &mut Context, image_sources: &[&str]) -> Result<Self> {
+        let mut bundle = Self::empty();
+        bundle.preload::<Image>(ctx, image_sources)?;
+        Ok(bundle)
+    }
+
+    /// Creates a bundle with the default loaders registered, then loads the top level images and
+    /// sheets listed in `manifest`. Groups are not loaded until requested via
+    /// `AssetBundle::load_group()`.
+    pub fn from_manifest(ctx: &mut Context, manifest: &Manifest) -> Result<Self> {
+        let mut bundle = Self::empty();
+        bundle.load_assets(ctx, &manifest.ungrouped())?;
+        Ok(bundle)
+    }
+
+    /// Loads the images and sheets in the manifest group `name`.
+    pub fn load_group(&mut self, ctx: &mut Context, manifest: &Manifest, name: &str) -> Result<()> {
+        self.load_assets(ctx, manifest.group(name)?)
+    }
+
+    /// Unloads the images and sheets in the manifest group `name`, invalidating any handles to
+    /// them.
+    pub fn unload_group(&mut self, manifest: &Manifest, name: &str) -> Result<()> {
+        let group = manifest.group(name)?;
+        for path in group.image_paths() {
+            self.unload::<Image>(path);
+        }
+        for path in group.sheet_paths() {
+            self.unload::<SpriteSheetData>(path);
+            self.unload::<ClipStore>(path);
+        }
+        Ok(())
+    }
+
+    /// Loads every image in `group`, plus the data of each sheet as both `SpriteSheetData` and a
+    /// `ClipStore`. Sheet data is only read once for both.
+    fn load_assets(&mut self, ctx: &mut Context, group: &Group) -> Result<()> {
+        let mut errors = vec![];
+        if let Err(e) = self.preload::<Image>(ctx, &group.image_paths()) {
+            match e {
+                Error::Multiple(errs) => errors.extend(errs),
+                e => errors.push(e),
+            }
+        }
+        for path in group.sheet_paths() {
+            if self.try_get::<SpriteSheetData>(path).is_some() {
+                continue;
+            }
+            match loaders::load_sheet(ctx, path) {
+                Ok(sheet) => {
+                    self.insert(path, ClipStore::new(&sheet));
+                    self.insert(path, sheet);
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Multiple(errors))
+        }
+    }
+
+    /// Like `AssetBundle::new`, but `fallback` is served in place of images which fail to load
+    /// (see `missing_texture()`), and the failures are kept for `AssetBundle::take_warnings()`.
+    pub fn with_fallback(ctx: &mut Context, image_sources: &[&str], fallback: Image) -> Self {
+        let mut bundle = Self::empty();
+        bundle.set_fallback(fallback);
+        for src in image_sources {
+            // Failures are recorded as warnings by `get()` when there's a fallback.
+            let _ = bundle.get_image(ctx, src);
+        }
+        bundle
+    }
+
+    /// Registers `loader` as the means to load assets of type `T`, replacing any loader
+    /// previously registered for `T`. Assets of type `T` which are already cached are kept.
+    pub fn register_loader<T: 'static, L: Loader<T> + 'static>(&mut self, loader: L) {
+        let loader: Box<dyn Loader<T>> = Box::new(loader);
+        if let Some(store) = self.store_mut::<T>() {
+            store.loader = loader;
+            return;
+        }
+        self.stores
+            .insert(TypeId::of::<T>(), Box::new(Store::new(loader)));
+    }
+
+    /// Sets the asset to serve in place of any asset of type `T` which fails to load. When a
+    /// fallback is set, load failures are kept as warnings (see `AssetBundle::take_warnings()`)
+    /// rather than returned as errors.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no loader is registered for `T`.
+    pub fn set_fallback<T: 'static>(&mut self, fallback: T) {
+        self.store_mut::<T>()
+            .expect("a loader must be registered before setting a fallback")
+            .fallback = Some(fallback);
+    }
+
+    fn store<T: 'static>(&self) -> Option<&Store<T>> {
+        self.stores
+            .get(&TypeId::of::<T>())
+            .and_then(|store| store.as_any().downcast_ref())
+    }
+
+    fn store_mut<T: 'static>(&mut self) -> Option<&mut Store<T>> {
+        self.stores
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|store| store.as_any_mut().downcast_mut())
+    }
+
+    fn loader_for<T: 'static>(&mut self, path: &str) -> Result<&mut Store<T>> {
+        self.store_mut::<T>().ok_or_else(|| {
+            Error::NoLoader { path: PathBuf::from(path) }
+        })
+    }
+
+    /// Returns the asset for `path`, loading and caching it the first time it is requested. It
+    /// stays cached until unloaded, whatever its reference count.
+    ///
+    /// If the asset can't be loaded and a fallback is set for `T`, the failure is kept as a
+    /// warning and the fallback is returned instead. The failure is remembered, so the load is
+    /// not retried on every request until the path is unloaded or the bundle is cleared.
+    pub fn get<T: 'static>(&mut self, ctx: &mut Context, path: &str) -> Result<&T> {
+        self.loader_for::<T>(path)?.get(ctx, path)
+    }
+
+    /// Returns the asset for `path` if it has already been loaded, without attempting to load
+    /// it.
+    pub fn try_get<T: 'static>(&self, path: &str) -> Option<&T> {
+        self.store::<T>().and_then(|store| {
+            store.index.get(path).and_then(|&idx| {
+                store.slots[idx].entry.as_ref().and_then(
+                    |entry| entry.asset.as_ref(),
+                )
+            })
+        })
+    }
+
+    /// Adds `asset` to the cache under `path`, replacing any asset already cached there (existing
+    /// handles will see the new asset). It stays cached until unloaded, whatever its reference
+    /// count.
+    ///
+    /// Assets of types with no registered loader can be added this way, though requests for
+    /// paths which were never inserted will fail.
+    pub fn insert<T: 'static>(&mut self, path: &str, asset: T) {
+        if self.store::<T>().is_none() {
+            self.register_loader::<T, _>(|_: &mut Context, path: &str| {
+                Err(Error::NoLoader { path: PathBuf::from(path) })
+            });
+        }
+        let store = self.store_mut::<T>().expect("a store was just registered");
+        let idx = store.put(path, asset);
+        store.pin(idx);
+    }
+
+    /// Returns a handle to the asset for `path`, loading it first if it's not already cached, and
+    /// adds one to its reference count.
+    ///
+    /// Each call should eventually be matched by a call to `AssetBundle::release()`.
+    pub fn acquire<T: 'static>(&mut self, ctx: &mut Context, path: &str) -> Result<Handle<T>> {
+        self.loader_for::<T>(path)?.acquire(ctx, path)
+    }
+
+    /// Returns the asset `handle` refers to, or `None` if it has since been freed.
+    ///
+    /// If the asset failed to load and a fallback is set for `T`, the fallback is returned.
+    pub fn resolve<T: 'static>(&self, handle: Handle<T>) -> Option<&T> {
+        self.store::<T>().and_then(|store| {
+            store.slot_for(handle).and_then(|idx| store.asset(idx))
+        })
+    }
+
+    /// Removes one from the reference count of the asset `handle` refers to, freeing the asset
+    /// when no references remain, unless it was also loaded by `AssetBundle::get()`,
+    /// `preload()` or `insert()`. Releasing a stale handle has no effect.
+    pub fn release<T: 'static>(&mut self, handle: Handle<T>) {
+        if let Some(store) = self.store_mut::<T>() {
+            store.release(handle);
+        }
+    }
+
+    /// Loads and caches each asset in `paths` which is not already cached. They stay cached until
+    /// unloaded, whatever their reference counts. If any fail to load, the error will list every
+    /// path that failed, not just the first.
+    ///
+    /// Failures are reported even if a fallback is set for `T`.
+    pub fn preload<T: 'static>(&mut self, ctx: &mut Context, paths: &[&str]) -> Result<()> {
+        let mut errors = vec![];
+        match self.store_mut::<T>() {
+            Some(store) => store.preload(ctx, paths, &mut errors),
+            None => {
+                errors.extend(paths.iter().map(|path| {
+                    Error::NoLoader { path: PathBuf::from(path) }
+                }))
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Multiple(errors))
+        }
+    }
+
+    /// Loads the asset for `path` again, replacing the cached copy. If the load fails, the
+    /// previously cached asset (if any) is kept.
+    pub fn reload<T: 'static>(&mut self, ctx: &mut Context, path: &str) -> Result<()> {
+        self.loader_for::<T>(path)?.reload(ctx, path)
+    }
+
+    /// Removes the asset for `path` from the cache, returning it if it was loaded.
+    ///
+    /// Any handles to the asset are invalidated, regardless of its reference count.
+    pub fn unload<T: 'static>(&mut self, path: &str) -> Option<T> {
+        self.store_mut::<T>().and_then(|store| store.unload(path))
+    }
+
+    /// The paths of every asset of type `T` requested from the bundle, including those which
+    /// failed to load and are being served by the fallback.
+    pub fn paths<T: 'static>(&self) -> Vec<&str> {
+        self.store::<T>().map_or(vec![], |store| store.paths())
+    }
+
+    /// Returns the load failures which were covered up by a fallback since the last call, so
+    /// they can be shown to the player or developer.
+    pub fn take_warnings(&mut self) -> Vec<Error> {
+        let mut warnings = vec![];
+        for store in self.stores.values_mut() {
+            warnings.extend(store.take_warnings());
+        }
+        warnings
+    }
+
+    /// Removes every asset, of every type, from the cache, invalidating all handles. Loaders and
+    /// fallbacks are retained.
+    pub fn clear(&mut self) {
+        for store in self.stores.values_mut() {
+            store.clear();
+        }
+    }
+
+    /// Shorthand for `AssetBundle::get::<Image>()`.
+    pub fn get_image(&mut self, ctx: &mut Context, path: &str) -> Result<&Image> {
+        self.get::<Image>(ctx, path)
+    }
+
+    /// Shorthand for `AssetBundle::acquire::<Image>()`.
+    pub fn acquire_image(&mut self, ctx: &mut Context, path: &str) -> Result<ImageHandle> {
+        self.acquire::<Image>(ctx, path)
+    }
+
+    /// Shorthand for `AssetBundle::try_get::<Image>()`.
+    pub fn try_get_image(&self, path: &str) -> Option<&Image> {
+        self.try_get::<Image>(path)
+    }
+
+    /// Shorthand for `AssetBundle::reload::<Image>()`.
+    pub fn reload_image(&mut self, ctx: &mut Context, path: &str) -> Result<()> {
+        self.reload::<Image>(ctx, path)
+    }
+
+    /// Shorthand for `AssetBundle::paths::<Image>()`.
+    pub fn image_paths(&self) -> Vec<&str> {
+        self.paths::<Image>()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn get_store() -> Store<u32> {
+        Store::new(Box::new(|_: &mut Context, path: &str| -> Result<u32> {
+            Err(Error::NoLoader { path: PathBuf::from(path) })
+        }))
+    }
+
+    fn resolve(store: &Store<u32>, handle: Handle<u32>) -> Option<u32> {
+        store.slot_for(handle).and_then(|idx| store.asset(idx)).cloned()
+    }
+
+    #[test]
+    fn test_release_frees_when_unreferenced() {
+        let mut store = get_store();
+        let idx = store.insert("/a.png", Some(1), false);
+        let first = store.retain(idx);
+        let second = store.retain(idx);
+        assert_eq!(first, second);
+
+        store.release(first);
+        assert_eq!(resolve(&store, second), Some(1));
+        store.release(second);
+        assert_eq!(resolve(&store, second), None);
+        assert!(store.paths().is_empty());
+    }
+
+    #[test]
+    fn test_pinned_assets_outlive_handles() {
+        let mut store = get_store();
+        let idx = store.put("/a.png", 1);
+        let handle = store.retain(idx);
+        store.release(handle);
+        assert_eq!(resolve(&store, handle), Some(1));
+
+        // the same goes for an acquired asset which is later pinned (as `preload()` does)
+        let idx = store.insert("/b.png", Some(2), false);
+        let handle = store.retain(idx);
+        store.pin(idx);
+        store.release(handle);
+        assert_eq!(resolve(&store, handle), Some(2));
+    }
+
+    #[test]
+    fn test_stale_handles_are_rejected() {
+        let mut store = get_store();
+        let idx = store.insert("/a.png", Some(1), false);
+        let stale = store.retain(idx);
+        store.release(stale);
+
+        // the freed slot is reused with a new generation
+        let idx = store.insert("/b.png", Some(2), false);
+        assert_eq!(idx, stale.index as usize);
+        let fresh = store.retain(idx);
+        assert_eq!(fresh.generation, stale.generation + 1);
+        assert_eq!(resolve(&store, stale), None);
+
+        // releasing the stale handle doesn't touch the new asset
+        store.release(stale);
+        assert_eq!(resolve(&store, fresh), Some(2));
+    }
+}
@@ -1,6 +1,37 @@
 use specs;
+use std::sync::Arc;
+use ggez::graphics::Color;
+use assets::ImageHandle;
+use sprites::{AnimationClip, SpriteSheetData};
 
 
+/// The point of a sprite which is placed at its `Body`'s position, and which it's scaled, rotated
+/// and flipped around.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Origin {
+    /// In pixels from the top-left corner of the frame.
+    Absolute(f32, f32),
+    /// As a fraction of the frame's size, so `Normalized(0.5, 0.5)` is its center.
+    Normalized(f32, f32),
+}
+
+impl Origin {
+    /// The origin as a fraction of a `width` by `height` frame.
+    pub fn normalized(&self, width: f32, height: f32) -> (f32, f32) {
+        match *self {
+            Origin::Absolute(x, y) => (x / width, y / height),
+            Origin::Normalized(x, y) => (x, y),
+        }
+    }
+}
+
+impl Default for Origin {
+    /// The top-left corner.
+    fn default() -> Self {
+        Origin::Normalized(0., 0.)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Body {
     pub x: f32,
@@ -8,17 +39,157 @@ pub struct Body {
     pub scale_x: f32,
     pub scale_y: f32,
     pub rotation: f32,
+    /// Used when the sprite's current frame has no pivot of its own (see
+    /// `aseprite::ExportData::pivot()`).
+    pub origin: Origin,
+    /// Mirrors the sprite, and any children, horizontally around its origin.
+    pub flip_x: bool,
+    /// Mirrors the sprite, and any children, vertically around its origin.
+    pub flip_y: bool,
+}
+
+impl Body {
+    /// An unscaled, unrotated body at `x`, `y`, with its origin at the top-left corner.
+    pub fn new(x: f32, y: f32) -> Self {
+        Body {
+            x: x,
+            y: y,
+            scale_x: 1.,
+            scale_y: 1.,
+            rotation: 0.,
+            origin: Origin::default(),
+            flip_x: false,
+            flip_y: false,
+        }
+    }
+
+    /// The scale, negated along each flipped axis.
+    pub fn signed_scale(&self) -> (f32, f32) {
+        (
+            if self.flip_x { -self.scale_x } else { self.scale_x },
+            if self.flip_y { -self.scale_y } else { self.scale_y },
+        )
+    }
 }
 
 impl specs::Component for Body {
     type Storage = specs::VecStorage<Body>;
 }
 
+/// Makes an entity's `Body` relative to that of another entity, so it moves, scales and rotates
+/// along with its parent.
+#[derive(Clone, Debug)]
+pub struct Parent {
+    pub entity: specs::Entity,
+}
+
+impl specs::Component for Parent {
+    type Storage = specs::HashMapStorage<Parent>;
+}
+
+/// `GlobalTransform` is an entity's `Body` composed with those of its ancestors, as computed by
+/// `systems::TransformPropagation`. It shouldn't be modified directly.
+///
+/// Non-uniform scale is applied along the entity's own axes, so a non-uniformly scaled parent
+/// doesn't skew its rotated children. Flips are folded into the scale, which is negative along
+/// flipped axes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GlobalTransform {
+    pub x: f32,
+    pub y: f32,
+    pub scale_x: f32,
+    pub scale_y: f32,
+    pub rotation: f32,
+}
+
+impl GlobalTransform {
+    pub fn identity() -> Self {
+        GlobalTransform {
+            x: 0.,
+            y: 0.,
+            scale_x: 1.,
+            scale_y: 1.,
+            rotation: 0.,
+        }
+    }
+
+    /// The transform of a child whose `Body` is `local`, relative to this transform.
+    pub fn compose(&self, local: &Body) -> GlobalTransform {
+        let (sin, cos) = self.rotation.sin_cos();
+        let (x, y) = (local.x * self.scale_x, local.y * self.scale_y);
+        let (scale_x, scale_y) = local.signed_scale();
+        let mirrored = (self.scale_x < 0.) != (self.scale_y < 0.);
+        GlobalTransform {
+            x: self.x + x * cos - y * sin,
+            y: self.y + x * sin + y * cos,
+            scale_x: self.scale_x * scale_x,
+            scale_y: self.scale_y * scale_y,
+            // a mirrored parent turns its children the other way
+            rotation: if mirrored {
+                self.rotation - local.rotation
+            } else {
+                self.rotation + local.rotation
+            },
+        }
+    }
+}
+
+impl<'a> From<&'a Body> for GlobalTransform {
+    fn from(body: &'a Body) -> Self {
+        GlobalTransform::identity().compose(body)
+    }
+}
+
+impl specs::Component for GlobalTransform {
+    type Storage = specs::VecStorage<GlobalTransform>;
+}
+
+/// Sprites are always alpha blended, as ggez 0.3 has no way to change the blend mode.
 #[derive(Clone, Debug)]
 pub struct Sprited {
-    pub path: String,
+    pub image: ImageHandle,
+    /// Sprites on higher layers are drawn over those on lower layers.
+    pub layer: i32,
+    /// Orders sprites within a layer; higher depths are drawn later (on top).
+    pub depth: f32,
+    /// Multiplied with the sprite's pixels. White leaves them as they are.
+    pub tint: Color,
+    /// The sprite's opacity, from 0 (invisible) to 1, multiplied with the tint's alpha. Kept
+    /// apart from the tint so fades and color changes don't interfere with each other.
+    pub alpha: f32,
+}
+
+impl Sprited {
+    /// An untinted, opaque sprite on layer 0, at depth 0.
+    pub fn new(image: ImageHandle) -> Self {
+        Sprited {
+            image: image,
+            layer: 0,
+            depth: 0.,
+            tint: Color::new(1., 1., 1., 1.),
+            alpha: 1.,
+        }
+    }
+
+    /// The color to draw the sprite with: its tint, with `alpha` applied.
+    pub fn color(&self) -> Color {
+        Color::new(self.tint.r, self.tint.g, self.tint.b, self.tint.a * self.alpha)
+    }
 }
 
 impl specs::Component for Sprited {
     type Storage = specs::VecStorage<Sprited>;
 }
+
+/// `Animated` entities play an `AnimationClip`, advanced each frame by `systems::Animator`. The
+/// clip's current cell is used as the frame when the entity is rendered.
+#[derive(Clone, Debug)]
+pub struct Animated {
+    pub clip: AnimationClip,
+    /// The sheet the clip's cell indexes refer to.
+    pub sheet: Arc<SpriteSheetData>,
+}
+
+impl specs::Component for Animated {
+    type Storage = specs::VecStorage<Animated>;
+}
@@ -0,0 +1,144 @@
+//! The `delta` module contains `Delta`, the span of time used throughout the crate.
+
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Rem, Sub, SubAssign};
+use std::time::Duration;
+
+/// `Delta` is a span of game time, such as the length of a frame or of an animation.
+///
+/// Deltas are only made and read with explicit units, so seconds can't be mistaken for
+/// milliseconds. Unlike `std::time::Duration`, they may be negative.
+///
+/// They are stored as milliseconds, which is also how they are (de)serialized, to match the frame
+/// durations in Aseprite's export data.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct Delta(f32);
+
+impl Delta {
+    pub fn zero() -> Self {
+        Delta(0.)
+    }
+
+    pub fn from_millis(millis: f32) -> Self {
+        Delta(millis)
+    }
+
+    pub fn from_secs(secs: f32) -> Self {
+        Delta(secs * 1000.)
+    }
+
+    pub fn as_millis(&self) -> f32 {
+        self.0
+    }
+
+    pub fn as_secs(&self) -> f32 {
+        self.0 / 1000.
+    }
+
+    pub fn abs(&self) -> Self {
+        Delta(self.0.abs())
+    }
+
+    /// Converts to a `Duration`. Negative deltas become zero, since durations can't be negative.
+    pub fn to_duration(&self) -> Duration {
+        let millis = self.0.max(0.);
+        let secs = (millis / 1000.).floor();
+        Duration::new(secs as u64, ((millis - secs * 1000.) * 1e6) as u32)
+    }
+}
+
+impl From<Duration> for Delta {
+    fn from(duration: Duration) -> Self {
+        Delta(duration.as_secs() as f32 * 1000. + duration.subsec_nanos() as f32 / 1e6)
+    }
+}
+
+impl Add for Delta {
+    type Output = Delta;
+    fn add(self, other: Delta) -> Delta {
+        Delta(self.0 + other.0)
+    }
+}
+
+impl AddAssign for Delta {
+    fn add_assign(&mut self, other: Delta) {
+        self.0 += other.0;
+    }
+}
+
+impl Sub for Delta {
+    type Output = Delta;
+    fn sub(self, other: Delta) -> Delta {
+        Delta(self.0 - other.0)
+    }
+}
+
+impl SubAssign for Delta {
+    fn sub_assign(&mut self, other: Delta) {
+        self.0 -= other.0;
+    }
+}
+
+impl Neg for Delta {
+    type Output = Delta;
+    fn neg(self) -> Delta {
+        Delta(-self.0)
+    }
+}
+
+impl Mul<f32> for Delta {
+    type Output = Delta;
+    fn mul(self, factor: f32) -> Delta {
+        Delta(self.0 * factor)
+    }
+}
+
+impl Div<f32> for Delta {
+    type Output = Delta;
+    fn div(self, divisor: f32) -> Delta {
+        Delta(self.0 / divisor)
+    }
+}
+
+/// Dividing one span by another gives their ratio.
+impl Div for Delta {
+    type Output = f32;
+    fn div(self, other: Delta) -> f32 {
+        self.0 / other.0
+    }
+}
+
+impl Rem for Delta {
+    type Output = Delta;
+    fn rem(self, other: Delta) -> Delta {
+        Delta(self.0 % other.0)
+    }
+}
+
+impl Sum for Delta {
+    fn sum<I: Iterator<Item = Delta>>(iter: I) -> Delta {
+        iter.fold(Delta::zero(), Add::add)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json;
+
+    #[test]
+    fn test_units() {
+        assert_eq!(Delta::from_secs(1.5), Delta::from_millis(1500.));
+        assert_eq!(Delta::from_millis(250.).as_secs(), 0.25);
+        assert_eq!(Delta::from(Duration::from_millis(2500)).as_millis(), 2500.);
+        assert_eq!(Delta::from_secs(1.) / Delta::from_millis(250.), 4.);
+        assert_eq!(Delta::from_millis(2500.).to_duration(), Duration::from_millis(2500));
+        assert_eq!(Delta::from_millis(-5.).to_duration(), Duration::from_millis(0));
+    }
+
+    #[test]
+    fn test_deserializes_from_millis() {
+        let delta: Delta = serde_json::from_str("100").unwrap();
+        assert_eq!(delta, Delta::from_millis(100.));
+    }
+}
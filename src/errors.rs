@@ -0,0 +1,220 @@
+//! The `errors` module contains the error type returned by the fallible loaders in this crate.
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::result;
+use ggez::GameError;
+use image::ImageError;
+#[cfg(feature = "hot-reload")]
+use notify;
+use serde_json;
+use serde_json::error::Category;
+
+pub type Result<T> = result::Result<T, Error>;
+
+/// `Error` describes what went wrong while loading data, and where.
+///
+/// Each variant carries the path of the file being read (when the data came from a file) so
+/// failures can be reported in terms an artist or designer can act on.
+#[derive(Debug)]
+pub enum Error {
+    /// The file could not be opened or read.
+    Io {
+        path: Option<PathBuf>,
+        cause: io::Error,
+    },
+    /// The data was not well-formed JSON. `line` and `column` point at the offending character.
+    Syntax {
+        path: Option<PathBuf>,
+        line: usize,
+        column: usize,
+        cause: serde_json::Error,
+    },
+    /// The data was well-formed JSON, but did not have the expected structure (missing fields,
+    /// wrong types, etc).
+    Schema {
+        path: Option<PathBuf>,
+        line: usize,
+        column: usize,
+        cause: serde_json::Error,
+    },
+    /// A frame tag points at frames that are not present in the export.
+    TagOutOfRange {
+        path: Option<PathBuf>,
+        tag: String,
+        from: usize,
+        to: usize,
+        frame_count: usize,
+    },
+    /// ggez was unable to load the asset at `path`.
+    Asset { path: PathBuf, cause: GameError },
+    /// The image at `path` could not be decoded.
+    Decode { path: PathBuf, cause: ImageError },
+    /// An asset was requested for a type which has no loader registered.
+    NoLoader { path: PathBuf },
+    /// A manifest has no group with the given name.
+    UnknownGroup(String),
+    /// An animation graph refers to a clip that isn't in the sprite sheet.
+    UnknownClip(String),
+    /// An animation graph refers to a state it doesn't define.
+    UnknownState(String),
+    /// Several loads failed at once. Each failure is reported separately.
+    Multiple(Vec<Error>),
+    /// The file watcher could not be started or could not watch `path`.
+    #[cfg(feature = "hot-reload")]
+    Watch {
+        path: Option<PathBuf>,
+        cause: notify::Error,
+    },
+}
+
+impl Error {
+    /// Wraps a `serde_json::Error`, sorting it into the appropriate variant.
+    pub fn from_json(cause: serde_json::Error, path: Option<&Path>) -> Self {
+        let path = path.map(|p| p.to_path_buf());
+        let (line, column) = (cause.line(), cause.column());
+        match cause.classify() {
+            Category::Io => Error::Io {
+                path: path,
+                cause: cause.into(),
+            },
+            Category::Syntax | Category::Eof => Error::Syntax {
+                path: path,
+                line: line,
+                column: column,
+                cause: cause,
+            },
+            Category::Data => Error::Schema {
+                path: path,
+                line: line,
+                column: column,
+                cause: cause,
+            },
+        }
+    }
+
+    /// The file the error originated from, if the data was read from a file.
+    pub fn path(&self) -> Option<&Path> {
+        match *self {
+            Error::Io { ref path, .. } |
+            Error::Syntax { ref path, .. } |
+            Error::Schema { ref path, .. } |
+            Error::TagOutOfRange { ref path, .. } => path.as_ref().map(|p| p.as_path()),
+            Error::Asset { ref path, .. } |
+            Error::Decode { ref path, .. } |
+            Error::NoLoader { ref path } => Some(path.as_path()),
+            Error::UnknownGroup(_) |
+            Error::UnknownClip(_) |
+            Error::UnknownState(_) |
+            Error::Multiple(_) => None,
+            #[cfg(feature = "hot-reload")]
+            Error::Watch { ref path, .. } => path.as_ref().map(|p| p.as_path()),
+        }
+    }
+
+    /// The line and column in the JSON input where the error was detected, if known.
+    pub fn location(&self) -> Option<(usize, usize)> {
+        match *self {
+            Error::Syntax { line, column, .. } |
+            Error::Schema { line, column, .. } if line > 0 => Some((line, column)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Error::Multiple(ref errors) = *self {
+            write!(f, "{} errors occurred while loading", errors.len())?;
+            for err in errors {
+                write!(f, "\n  {}", err)?;
+            }
+            return Ok(());
+        }
+        match self.path() {
+            Some(path) => write!(f, "{}: ", path.display())?,
+            // Only errors about the contents of some input can point at it.
+            None => match *self {
+                Error::UnknownGroup(_) |
+                Error::UnknownClip(_) |
+                Error::UnknownState(_) => {}
+                _ => write!(f, "<input>: ")?,
+            },
+        }
+        match *self {
+            Error::Io { ref cause, .. } => write!(f, "unable to read file ({})", cause),
+            Error::Syntax { ref cause, .. } => write!(f, "malformed JSON ({})", cause),
+            Error::Schema { ref cause, .. } => write!(f, "unexpected JSON structure ({})", cause),
+            Error::TagOutOfRange {
+                ref tag,
+                from,
+                to,
+                frame_count,
+                ..
+            } => write!(
+                f,
+                "frame tag `{}` spans frames {}..{} but only {} frames are defined",
+                tag,
+                from,
+                to,
+                frame_count
+            ),
+            Error::Asset { ref cause, .. } => write!(f, "unable to load asset ({})", cause),
+            Error::Decode { ref cause, .. } => write!(f, "unable to decode image ({})", cause),
+            Error::NoLoader { .. } => write!(f, "no loader is registered for this type of asset"),
+            Error::UnknownGroup(ref name) => write!(f, "no asset group named `{}`", name),
+            Error::UnknownClip(ref name) => write!(f, "no clip named `{}`", name),
+            Error::UnknownState(ref name) => write!(f, "no animation state named `{}`", name),
+            Error::Multiple(_) => unreachable!(),
+            #[cfg(feature = "hot-reload")]
+            Error::Watch { ref cause, .. } => write!(f, "unable to watch for changes ({})", cause),
+        }
+    }
+}
+
+impl StdError for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::Io { .. } => "unable to read file",
+            Error::Syntax { .. } => "malformed JSON",
+            Error::Schema { .. } => "unexpected JSON structure",
+            Error::TagOutOfRange { .. } => "frame tag out of range",
+            Error::Asset { .. } => "unable to load asset",
+            Error::Decode { .. } => "unable to decode image",
+            Error::NoLoader { .. } => "no loader registered",
+            Error::UnknownGroup(_) => "unknown asset group",
+            Error::UnknownClip(_) => "unknown clip",
+            Error::UnknownState(_) => "unknown animation state",
+            Error::Multiple(_) => "multiple errors occurred while loading",
+            #[cfg(feature = "hot-reload")]
+            Error::Watch { .. } => "unable to watch for changes",
+        }
+    }
+
+    fn cause(&self) -> Option<&StdError> {
+        match *self {
+            Error::Io { ref cause, .. } => Some(cause),
+            Error::Syntax { ref cause, .. } | Error::Schema { ref cause, .. } => Some(cause),
+            Error::Asset { ref cause, .. } => Some(cause),
+            Error::Decode { ref cause, .. } => Some(cause),
+            #[cfg(feature = "hot-reload")]
+            Error::Watch { ref cause, .. } => Some(cause),
+            Error::TagOutOfRange { .. } |
+            Error::NoLoader { .. } |
+            Error::UnknownGroup(_) |
+            Error::UnknownClip(_) |
+            Error::UnknownState(_) |
+            Error::Multiple(_) => None,
+        }
+    }
+}
+
+/// Allows loader errors to be propagated with `?` from functions returning `ggez::GameResult`,
+/// such as the methods of `ggez::event::EventHandler`.
+impl From<Error> for GameError {
+    fn from(err: Error) -> GameError {
+        GameError::ResourceLoadError(err.to_string())
+    }
+}
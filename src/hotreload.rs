@@ -0,0 +1,230 @@
+//! The `hotreload` module watches the files behind images and sprite sheet data so changes made
+//! while the game is running can be picked up without a restart.
+//!
+//! This module is only available when the `hot-reload` feature is enabled, and is intended for
+//! use during development.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! extern crate ggez;
+//! extern crate omn_labs;
+//!
+//! use ggez::Context;
+//! use omn_labs::assets::AssetBundle;
+//! use omn_labs::hotreload::{Change, HotReloader};
+//! use omn_labs::sprites::{AnimationClip, ClipStore, SpriteSheetData};
+//!
+//! fn check_for_changes(
+//!     ctx: &mut Context,
+//!     reloader: &mut HotReloader,
+//!     assets: &mut AssetBundle,
+//!     clips: &mut ClipStore,
+//!     clip: &mut AnimationClip,
+//! ) {
+//!     // Also starts watching any images loaded into `assets` since the last poll.
+//!     for change in reloader.poll_bundle(assets) {
+//!         match change {
+//!             Ok(Change::Image(path)) => {
+//!                 if let Err(e) = assets.reload_image(ctx, &path) {
+//!                     println!("{}", e);
+//!                 }
+//!             }
+//!             Ok(Change::Sheet(path)) => match SpriteSheetData::from_file(&path) {
+//!                 Ok(sheet) => {
+//!                     *clips = ClipStore::new(&sheet);
+//!                     clips.refresh(clip);
+//!                 }
+//!                 Err(e) => println!("{}", e),
+//!             },
+//!             Err(e) => println!("{}", e),
+//!         }
+//!     }
+//! }
+//!
+//! fn main() {
+//!     // With ggez's `cargo-resource-root` feature, this is where image paths are resolved.
+//!     let mut reloader =
+//!         HotReloader::new(concat!(env!("CARGO_MANIFEST_DIR"), "/resources")).unwrap();
+//!     reloader.watch_image("/numbers-matrix.png").unwrap();
+//!     reloader.watch_sheet("resources/numbers/numbers-matrix-tags.array.json").unwrap();
+//! }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use std::time::Duration;
+
+use notify::{self, DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher};
+
+use assets::{self, AssetBundle};
+use errors::{Error, Result};
+
+/// How long to wait for a burst of file system events to settle before reporting a change.
+const DEBOUNCE_MILLIS: u64 = 200;
+
+/// A file which has changed on disk since the last call to `HotReloader::poll()`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Change {
+    /// An image, identified by its resource path (as given to `AssetBundle`).
+    Image(String),
+    /// Sprite sheet data, identified by the path given to `HotReloader::watch_sheet()`.
+    Sheet(PathBuf),
+}
+
+/// `HotReloader` watches image and sprite sheet files for changes.
+///
+/// Rather than watching each file directly, the directory containing it is watched. Many tools
+/// (Aseprite included) save by writing a new file and renaming it over the old one, which would
+/// otherwise stop the watch from seeing later changes.
+pub struct HotReloader {
+    watcher: RecommendedWatcher,
+    rx: Receiver<DebouncedEvent>,
+    resource_root: PathBuf,
+    watched_dirs: HashSet<PathBuf>,
+    /// The resource paths of the images being watched.
+    images: HashSet<String>,
+    /// Maps files on disk to the change to report when they are modified.
+    files: HashMap<PathBuf, Change>,
+}
+
+impl HotReloader {
+    /// `resource_root` is the directory on disk which image resource paths are relative to.
+    pub fn new<P: AsRef<Path>>(resource_root: P) -> Result<Self> {
+        let (tx, rx) = channel();
+        let watcher = notify::watcher(tx, Duration::from_millis(DEBOUNCE_MILLIS))
+            .map_err(|e| Error::Watch { path: None, cause: e })?;
+        Ok(HotReloader {
+            watcher: watcher,
+            rx: rx,
+            resource_root: resource_root.as_ref().to_path_buf(),
+            watched_dirs: HashSet::new(),
+            images: HashSet::new(),
+            files: HashMap::new(),
+        })
+    }
+
+    /// Watches the image at the resource path `path`.
+    pub fn watch_image(&mut self, path: &str) -> Result<()> {
+        let file = assets::resource_path(&self.resource_root, path);
+        self.watch(&file, Change::Image(path.to_string()))?;
+        self.images.insert(path.to_string());
+        Ok(())
+    }
+
+    /// Watches every image requested from `bundle` so far. If any can't be watched, the error
+    /// will list every one that failed, not just the first.
+    ///
+    /// Images loaded into the bundle later aren't watched until this is called again; see
+    /// `HotReloader::poll_bundle()`.
+    pub fn watch_bundle(&mut self, bundle: &AssetBundle) -> Result<()> {
+        let errors = self.watch_new_images(bundle);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Multiple(errors))
+        }
+    }
+
+    fn watch_new_images(&mut self, bundle: &AssetBundle) -> Vec<Error> {
+        let mut errors = vec![];
+        for path in bundle.image_paths() {
+            if !self.images.contains(path) {
+                if let Err(e) = self.watch_image(path) {
+                    errors.push(e);
+                }
+            }
+        }
+        errors
+    }
+
+    /// Watches the sprite sheet data at `path`, as given to `SpriteSheetData::from_file()`.
+    pub fn watch_sheet<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        self.watch(path, Change::Sheet(path.to_path_buf()))
+    }
+
+    fn watch(&mut self, file: &Path, change: Change) -> Result<()> {
+        let file = normalize(file);
+        let dir = match file.parent() {
+            Some(dir) => dir.to_path_buf(),
+            None => PathBuf::from("."),
+        };
+        if !self.watched_dirs.contains(&dir) {
+            self.watcher
+                .watch(&dir, RecursiveMode::NonRecursive)
+                .map_err(|e| {
+                    Error::Watch {
+                        path: Some(dir.clone()),
+                        cause: e,
+                    }
+                })?;
+            self.watched_dirs.insert(dir);
+        }
+        self.files.insert(file, change);
+        Ok(())
+    }
+
+    /// Returns each watched file which has changed since the last poll, along with any errors
+    /// the watcher ran into. Each file is reported at most once per call, no matter how many
+    /// times it was written to.
+    pub fn poll(&self) -> Vec<Result<Change>> {
+        let mut changes: Vec<Result<Change>> = vec![];
+        for event in self.rx.try_iter() {
+            let path = match event {
+                DebouncedEvent::Write(path) |
+                DebouncedEvent::Create(path) |
+                DebouncedEvent::Rename(_, path) => path,
+                DebouncedEvent::Error(e, path) => {
+                    changes.push(Err(Error::Watch {
+                        path: path,
+                        cause: e,
+                    }));
+                    continue;
+                }
+                _ => continue,
+            };
+            if let Some(change) = self.files.get(&normalize(&path)) {
+                let reported = changes.iter().any(|c| match *c {
+                    Ok(ref c) => c == change,
+                    Err(_) => false,
+                });
+                if !reported {
+                    changes.push(Ok(change.clone()));
+                }
+            }
+        }
+        changes
+    }
+
+    /// Like `HotReloader::poll()`, but first watches any images loaded into `bundle` since the
+    /// last call, so lazily loaded images are picked up too. Failures to watch them are
+    /// returned with the changes.
+    pub fn poll_bundle(&mut self, bundle: &AssetBundle) -> Vec<Result<Change>> {
+        let mut changes: Vec<Result<Change>> =
+            self.watch_new_images(bundle).into_iter().map(Err).collect();
+        changes.extend(self.poll());
+        changes
+    }
+}
+
+/// Resolves `path` to an absolute path where possible so paths from the watcher can be compared
+/// with the paths we were asked to watch.
+fn normalize(path: &Path) -> PathBuf {
+    // The file itself may be missing in the middle of a save, so only the directory is resolved.
+    match (path.parent(), path.file_name()) {
+        (Some(dir), Some(name)) => {
+            let dir = if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            };
+            match dir.canonicalize() {
+                Ok(dir) => dir.join(name),
+                Err(_) => path.to_path_buf(),
+            }
+        }
+        _ => path.to_path_buf(),
+    }
+}
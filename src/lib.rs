@@ -4,15 +4,25 @@
 extern crate time;
 extern crate specs;
 extern crate ggez;
+extern crate image;
 
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 extern crate serde_json;
+#[cfg(feature = "hot-reload")]
+extern crate notify;
 
+mod delta;
+pub mod errors;
 pub mod components;
+pub mod render;
+pub mod resources;
 pub mod systems;
 pub mod assets;
 pub mod sprites;
+pub mod timing;
+#[cfg(feature = "hot-reload")]
+pub mod hotreload;
 
-pub type Delta = f32;
+pub use delta::Delta;
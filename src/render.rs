@@ -0,0 +1,214 @@
+//! The `render` module turns the `DrawCommand`s produced by `systems::Renderer` into ggez draw
+//! calls.
+
+use std::mem;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use ggez::{Context, GameResult};
+use ggez::graphics::{self, DrawParam, Image, Point, Rect};
+use assets::AssetBundle;
+use sprites::Region;
+use systems::DrawCommand;
+
+/// Converts `region`, in pixels, to the normalized rect ggez expects for `DrawParam.src`, given
+/// the dimensions of the image it's taken from.
+pub fn src_rect(region: &Region, image_width: f32, image_height: f32) -> Rect {
+    Rect::new(
+        region.x as f32 / image_width,
+        region.y as f32 / image_height,
+        region.width as f32 / image_width,
+        region.height as f32 / image_height,
+    )
+}
+
+/// Builds the `DrawParam` for `cmd`, given the dimensions of the image it draws. Returns `None`
+/// for commands which don't draw anything.
+pub fn draw_param(cmd: &DrawCommand, image_width: f32, image_height: f32) -> Option<DrawParam> {
+    match *cmd {
+        DrawCommand::DrawTransformed {
+            ref src,
+            origin,
+            x,
+            y,
+            rot,
+            sx,
+            sy,
+            ..
+        } => {
+            let (src, width, height) = match *src {
+                Some(ref region) => (
+                    src_rect(region, image_width, image_height),
+                    region.width as f32,
+                    region.height as f32,
+                ),
+                None => (Rect::one(), image_width, image_height),
+            };
+            // ggez 0.3 draws images centered on `dest`, and takes the point to transform around
+            // as a pixel offset from the center. `draw_ex` multiplies that by the scale (so it
+            // follows flips), and its shader has y pointing up, so y is negated here.
+            let (ox, oy) = origin.normalized(width, height);
+            Some(DrawParam {
+                src: src,
+                dest: Point::new(x, y),
+                rotation: rot,
+                scale: Point::new(sx, sy),
+                offset: Point::new((ox - 0.5) * width, (0.5 - oy) * height),
+                ..Default::default()
+            })
+        }
+        DrawCommand::Flush => None,
+    }
+}
+
+/// Draws `cmd`, resolving its image from `assets`. Commands whose image is no longer in the
+/// bundle are skipped.
+///
+/// This leaves the context's current color set to the command's color.
+pub fn execute(ctx: &mut Context, assets: &AssetBundle, cmd: &DrawCommand) -> GameResult<()> {
+    if let DrawCommand::DrawTransformed { image, color, .. } = *cmd {
+        if let Some(image) = assets.resolve::<Image>(image) {
+            if let Some(param) = draw_param(cmd, image.width() as f32, image.height() as f32) {
+                graphics::set_color(ctx, color)?;
+                graphics::draw_ex(ctx, image, param)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `Executor` receives the `DrawCommand`s sent by `systems::Renderer` and draws them with the
+/// images in its `AssetBundle`. It's intended to be called from `ggez::event::EventHandler::draw`.
+///
+/// Commands are grouped into frames by `DrawCommand::Flush`. Each call to `Executor::draw()`
+/// draws the most recently completed frame, so the screen is stable even if the world was
+/// updated several times (or not at all) between draws.
+///
+/// Each sprite is drawn with its own draw call. ggez 0.3's `SpriteBatch` also draws its sprites
+/// one at a time, so there's nothing to gain from batching them until ggez is upgraded.
+pub struct Executor {
+    rx: Receiver<DrawCommand>,
+    assets: AssetBundle,
+    /// Commands received since the last `Flush`.
+    pending: Vec<DrawCommand>,
+    /// The most recently completed frame.
+    frame: Vec<DrawCommand>,
+}
+
+impl Executor {
+    pub fn new(rx: Receiver<DrawCommand>, assets: AssetBundle) -> Self {
+        Executor {
+            rx: rx,
+            assets: assets,
+            pending: vec![],
+            frame: vec![],
+        }
+    }
+
+    /// Creates an `Executor` along with the `Sender` to give to `systems::Renderer`.
+    pub fn channel(assets: AssetBundle) -> (Self, Sender<DrawCommand>) {
+        let (tx, rx) = channel();
+        (Self::new(rx, assets), tx)
+    }
+
+    pub fn assets(&self) -> &AssetBundle {
+        &self.assets
+    }
+
+    pub fn assets_mut(&mut self) -> &mut AssetBundle {
+        &mut self.assets
+    }
+
+    /// Receives any waiting commands, then draws the most recently completed frame.
+    pub fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        for cmd in self.rx.try_iter() {
+            match cmd {
+                DrawCommand::Flush => {
+                    self.frame.clear();
+                    mem::swap(&mut self.frame, &mut self.pending);
+                }
+                cmd => self.pending.push(cmd),
+            }
+        }
+        let previous_color = graphics::get_color(ctx);
+        let drawn = self.draw_frame(ctx);
+        graphics::set_color(ctx, previous_color)?;
+        drawn
+    }
+
+    fn draw_frame(&self, ctx: &mut Context) -> GameResult<()> {
+        for cmd in &self.frame {
+            execute(ctx, &self.assets, cmd)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ggez::graphics::Color;
+    use assets::ImageHandle;
+    use components::Origin;
+
+    #[test]
+    fn test_src_rect_is_normalized() {
+        let region = Region {
+            x: 32,
+            y: 64,
+            width: 32,
+            height: 32,
+        };
+        let rect = src_rect(&region, 128., 256.);
+        assert_eq!(
+            (rect.x, rect.y, rect.w, rect.h),
+            (0.25, 0.25, 0.25, 0.125)
+        );
+    }
+
+    #[test]
+    fn test_origin_is_relative_to_src() {
+        let cmd = DrawCommand::DrawTransformed {
+            image: ImageHandle::from_raw(0, 0),
+            frame: 0,
+            src: Some(Region {
+                x: 32,
+                y: 0,
+                width: 32,
+                height: 16,
+            }),
+            origin: Origin::Absolute(8., 16.),
+            color: Color::new(1., 1., 1., 1.),
+            x: 0.,
+            y: 0.,
+            rot: 0.,
+            sx: -1.,
+            sy: 1.,
+        };
+        // 8px left of and 8px below the center of the 32x16 frame, with y negated for `draw_ex`
+        let param = draw_param(&cmd, 64., 64.).unwrap();
+        assert_eq!((param.offset.x, param.offset.y), (-8., -8.));
+        assert_eq!(param.scale.x, -1.);
+    }
+
+    #[test]
+    fn test_origin_is_offset_from_center() {
+        let at = |origin: Origin| {
+            let cmd = DrawCommand::DrawTransformed {
+                image: ImageHandle::from_raw(0, 0),
+                frame: 0,
+                src: None,
+                origin: origin,
+                color: Color::new(1., 1., 1., 1.),
+                x: 0.,
+                y: 0.,
+                rot: 0.,
+                sx: 1.,
+                sy: 1.,
+            };
+            let param = draw_param(&cmd, 32., 16.).unwrap();
+            (param.offset.x, param.offset.y)
+        };
+        assert_eq!(at(Origin::Normalized(0.5, 0.5)), (0., 0.));
+        assert_eq!(at(Origin::default()), (-16., 8.));
+        assert_eq!(at(Origin::Absolute(32., 16.)), (16., -8.));
+    }
+}
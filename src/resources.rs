@@ -0,0 +1,170 @@
+//! The `resources` module contains types which are shared across systems via the specs `World`,
+//! rather than attached to entities.
+
+use std::mem;
+use std::time::Duration;
+use ggez::graphics::Rect;
+use specs::Entity;
+use sprites::ClipEvent;
+use Delta;
+
+/// `Time` tells systems about the passage of game time. It's kept up to date by
+/// `timing::GameLoop`.
+#[derive(Clone, Debug)]
+pub struct Time {
+    /// The game time covered by the current update. With a fixed timestep this is always the
+    /// step length.
+    pub delta: Delta,
+    /// The game time passed since the loop started.
+    pub elapsed: Duration,
+    /// The number of updates run so far, including the current one.
+    pub frame_count: u64,
+    /// How fast game time passes relative to real time; 0 pauses the game. Systems may change
+    /// this, and it takes effect from the next frame.
+    pub time_scale: f32,
+    /// How far the current frame is between the last update and the next, from 0 to 1, for
+    /// interpolating what's drawn.
+    pub alpha: f32,
+}
+
+impl Default for Time {
+    fn default() -> Self {
+        Time {
+            delta: Delta::zero(),
+            elapsed: Duration::from_millis(0),
+            frame_count: 0,
+            time_scale: 1.,
+            alpha: 0.,
+        }
+    }
+}
+
+/// `AnimationEvents` collects the events from each `Animated` entity's clip as
+/// `systems::Animator` runs, in the order they happened for each entity.
+///
+/// Events are kept until drained, so none are missed when `timing::GameLoop` runs several steps
+/// per frame. Whatever reacts to them (typically the game's `EventHandler::update`, after the
+/// loop has run) should call `AnimationEvents::drain()` once per frame.
+#[derive(Clone, Debug, Default)]
+pub struct AnimationEvents(pub Vec<(Entity, ClipEvent)>);
+
+impl AnimationEvents {
+    /// Takes every event collected since the last drain.
+    pub fn drain(&mut self) -> Vec<(Entity, ClipEvent)> {
+        mem::replace(&mut self.0, vec![])
+    }
+}
+
+/// `Camera` maps world coordinates (those of `components::Body`) to the screen. It is applied by
+/// `systems::Renderer`, which also skips sprites falling outside the viewport.
+#[derive(Clone, Debug)]
+pub struct Camera {
+    /// The world position shown at the center of the viewport.
+    pub x: f32,
+    pub y: f32,
+    /// How many pixels a world unit covers; values above 1 zoom in.
+    pub zoom: f32,
+    /// The camera's rotation, in radians. Rotating the camera clockwise turns the world
+    /// counter-clockwise on screen.
+    pub rotation: f32,
+    /// The area of the screen the world is drawn to, in pixels.
+    pub viewport: Rect,
+}
+
+impl Camera {
+    /// A camera filling a `width` by `height` screen, positioned so world coordinates match
+    /// screen coordinates.
+    pub fn new(width: f32, height: f32) -> Self {
+        Camera {
+            x: width / 2.,
+            y: height / 2.,
+            zoom: 1.,
+            rotation: 0.,
+            viewport: Rect::new(0., 0., width, height),
+        }
+    }
+
+    fn viewport_center(&self) -> (f32, f32) {
+        (
+            self.viewport.x + self.viewport.w / 2.,
+            self.viewport.y + self.viewport.h / 2.,
+        )
+    }
+
+    pub fn world_to_screen(&self, x: f32, y: f32) -> (f32, f32) {
+        let (sin, cos) = (-self.rotation).sin_cos();
+        let (dx, dy) = (x - self.x, y - self.y);
+        let (cx, cy) = self.viewport_center();
+        (
+            cx + (dx * cos - dy * sin) * self.zoom,
+            cy + (dx * sin + dy * cos) * self.zoom,
+        )
+    }
+
+    pub fn screen_to_world(&self, x: f32, y: f32) -> (f32, f32) {
+        let (sin, cos) = self.rotation.sin_cos();
+        let (cx, cy) = self.viewport_center();
+        let (dx, dy) = ((x - cx) / self.zoom, (y - cy) / self.zoom);
+        (
+            self.x + dx * cos - dy * sin,
+            self.y + dx * sin + dy * cos,
+        )
+    }
+
+    /// Whether a circle at the screen position `x`, `y` with the given `radius` (in pixels)
+    /// overlaps the viewport.
+    pub fn is_visible(&self, x: f32, y: f32, radius: f32) -> bool {
+        let v = &self.viewport;
+        x + radius >= v.x && x - radius <= v.x + v.w && y + radius >= v.y &&
+            y - radius <= v.y + v.h
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn assert_near(actual: (f32, f32), expected: (f32, f32)) {
+        assert!(
+            (actual.0 - expected.0).abs() < 1e-4 && (actual.1 - expected.1).abs() < 1e-4,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_default_camera_is_identity() {
+        let camera = Camera::new(300., 200.);
+        assert_near(camera.world_to_screen(10., 20.), (10., 20.));
+        assert_near(camera.screen_to_world(10., 20.), (10., 20.));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let camera = Camera {
+            x: 40.,
+            y: -25.,
+            zoom: 2.,
+            rotation: FRAC_PI_2,
+            viewport: Rect::new(100., 50., 300., 200.),
+        };
+        // the camera's position is shown at the center of the viewport
+        assert_near(camera.world_to_screen(40., -25.), (250., 150.));
+        // a quarter turn clockwise puts the world's +x axis on the screen's -y axis
+        assert_near(camera.world_to_screen(41., -25.), (250., 148.));
+
+        let screen = camera.world_to_screen(7., 13.);
+        assert_near(camera.screen_to_world(screen.0, screen.1), (7., 13.));
+    }
+
+    #[test]
+    fn test_is_visible() {
+        let camera = Camera::new(100., 100.);
+        assert!(camera.is_visible(50., 50., 0.));
+        assert!(camera.is_visible(-10., 50., 16.));
+        assert!(!camera.is_visible(-20., 50., 16.));
+        assert!(!camera.is_visible(50., 120., 16.));
+    }
+}
@@ -2,11 +2,13 @@
 //! data feature provided by [Aseprite](https://www.aseprite.org/).
 
 use std::fs::File;
+use std::io::Read;
 use std::path::Path;
 use serde_json;
+use errors::{Error, Result};
 
 #[allow(unused_imports)]
-use super::{Region, FrameTag, Frame};
+use super::{Region, FrameTag, Frame, Direction};
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub struct Dimensions {
@@ -16,11 +18,56 @@ pub struct Dimensions {
     pub height: i32,
 }
 
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Pivot {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// The state of a `Slice` from `SliceKey.frame` onward (until the slice's next key).
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct SliceKey {
+    pub frame: usize,
+    pub bounds: Region,
+    /// Relative to the top-left corner of `bounds`.
+    #[serde(default)]
+    pub pivot: Option<Pivot>,
+}
+
+/// A named area of the sprite, as drawn with Aseprite's slice tool.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Slice {
+    pub name: String,
+    pub keys: Vec<SliceKey>,
+}
+
+/// A cel's place in the sprite, along with its user data (set via "Cel Properties" in Aseprite).
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Cel {
+    pub frame: usize,
+    #[serde(default)]
+    pub data: Option<String>,
+}
+
+/// A layer of the sprite. Only layers whose cels have user data list them.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Layer {
+    pub name: String,
+    #[serde(default)]
+    pub cels: Vec<Cel>,
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub struct Meta {
     #[serde(rename = "frameTags")]
     pub frame_tags: Vec<FrameTag>,
     pub size: Dimensions,
+    /// Only present in data exported by Aseprite 1.2 or later.
+    #[serde(default)]
+    pub slices: Vec<Slice>,
+    /// Only present when exported with the "Layers" meta option.
+    #[serde(default)]
+    pub layers: Vec<Layer>,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
@@ -30,16 +77,85 @@ pub struct ExportData {
 }
 
 impl ExportData {
-    pub fn parse_str(json: &str) -> ExportData {
-        serde_json::from_str(json).unwrap()
+    pub fn parse_str(json: &str) -> Result<ExportData> {
+        serde_json::from_str(json)
+            .map_err(|e| Error::from_json(e, None))
+            .and_then(|data: ExportData| data.validate(None))
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<ExportData> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|e| {
+            Error::Io {
+                path: Some(path.to_path_buf()),
+                cause: e,
+            }
+        })?;
+        Self::from_reader(file, Some(path))
+    }
+
+    /// Reads export data from any `Read`, such as a file opened via ggez's filesystem. `path` is
+    /// only used to annotate errors.
+    pub fn from_reader<R: Read>(reader: R, path: Option<&Path>) -> Result<ExportData> {
+        serde_json::from_reader(reader)
+            .map_err(|e| Error::from_json(e, path))
+            .and_then(|data: ExportData| data.validate(path))
+    }
+
+    pub fn from_json_value(json: serde_json::Value) -> Result<ExportData> {
+        serde_json::from_value(json)
+            .map_err(|e| Error::from_json(e, None))
+            .and_then(|data: ExportData| data.validate(None))
+    }
+
+    /// The pivot for the frame at `frame`, in pixels from the frame's top-left corner, taken from
+    /// the first slice which has a pivot at that frame.
+    ///
+    /// Pivots are given relative to the untrimmed sprite, so this assumes the sheet was exported
+    /// without trimming.
+    pub fn pivot(&self, frame: usize) -> Option<(i32, i32)> {
+        self.meta.slices.iter().filter_map(|slice| {
+            slice
+                .keys
+                .iter()
+                .filter(|key| key.frame <= frame)
+                .max_by_key(|key| key.frame)
+                .and_then(|key| {
+                    key.pivot.as_ref().map(|p| (key.bounds.x + p.x, key.bounds.y + p.y))
+                })
+        }).next()
     }
 
-    pub fn from_file<P: AsRef<Path>>(path: P) -> ExportData {
-        serde_json::from_reader(File::open(path).unwrap()).unwrap()
+    /// The user data of each cel in the frame at `frame`, across all layers.
+    pub fn markers(&self, frame: usize) -> Vec<&str> {
+        self.meta
+            .layers
+            .iter()
+            .flat_map(|layer| layer.cels.iter())
+            .filter(|cel| cel.frame == frame)
+            .filter_map(|cel| cel.data.as_ref())
+            .filter(|data| !data.is_empty())
+            .map(|data| data.as_str())
+            .collect()
     }
 
-    pub fn from_json_value(json: serde_json::Value) -> ExportData {
-        serde_json::from_value(json).unwrap()
+    /// Checks the frame tags refer to frames which actually exist.
+    fn validate(self, path: Option<&Path>) -> Result<ExportData> {
+        let frame_count = self.frames.len();
+        if let Some(tag) = self.meta
+            .frame_tags
+            .iter()
+            .find(|tag| tag.from > tag.to || tag.to >= frame_count)
+        {
+            return Err(Error::TagOutOfRange {
+                path: path.map(|p| p.to_path_buf()),
+                tag: tag.name.clone(),
+                from: tag.from,
+                to: tag.to,
+                frame_count: frame_count,
+            });
+        }
+        Ok(self)
     }
 }
 
@@ -48,12 +164,13 @@ mod test {
     use serde_json;
 
     use super::*;
+    use Delta;
 
     fn get_alpha() -> ExportData {
         ExportData {
             frames: vec![
                 Frame {
-                    duration: 1000,
+                    duration: Delta::from_millis(1000.),
                     bbox: Region {
                         x: 0,
                         y: 0,
@@ -62,7 +179,7 @@ mod test {
                     },
                 },
                 Frame {
-                    duration: 1000,
+                    duration: Delta::from_millis(1000.),
                     bbox: Region {
                         x: 32,
                         y: 0,
@@ -77,13 +194,16 @@ mod test {
                         name: "Alpha".to_string(),
                         from: 0,
                         to: 1,
-                        direction: "forward".to_string(),
+                        direction: Direction::Forward,
+                        data: None,
                     },
                 ],
                 size: Dimensions {
                     width: 64,
                     height: 32,
                 },
+                slices: vec![],
+                layers: vec![],
             },
         }
     }
@@ -118,4 +238,113 @@ mod test {
         assert_eq!(expected.meta.frame_tags, result.meta.frame_tags);
         assert_eq!(expected.meta.size, result.meta.size);
     }
+
+    #[test]
+    fn test_missing_file() {
+        match ExportData::from_file("resources/does-not-exist.json") {
+            Err(Error::Io { path, .. }) => {
+                assert_eq!(path, Some("resources/does-not-exist.json".into()))
+            }
+            other => panic!("expected an io error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_syntax_error_location() {
+        let err = ExportData::parse_str("{\n  \"frames\": [,]\n}").unwrap_err();
+        match err {
+            Error::Syntax { .. } => assert_eq!(err.location(), Some((2, 14))),
+            other => panic!("expected a syntax error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_schema_error() {
+        let err = ExportData::parse_str(r#"{ "frames": [] }"#).unwrap_err();
+        match err {
+            Error::Schema { .. } => assert!(err.location().is_some()),
+            other => panic!("expected a schema error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_unknown_direction() {
+        let json = r#"{
+          "frames": [
+            { "frame": { "x": 0, "y": 0, "w": 32, "h": 32 }, "duration": 1000 }
+          ],
+          "meta": {
+            "size": { "w": 32, "h": 32 },
+            "frameTags": [
+              { "name": "Alpha", "from": 0, "to": 0, "direction": "sideways" }
+            ]
+          }
+        }"#;
+        let err = ExportData::parse_str(json).unwrap_err();
+        match err {
+            Error::Schema { .. } => {
+                assert!(format!("{}", err).contains("sideways"));
+                assert_eq!(err.location().map(|(line, _)| line), Some(8));
+            }
+            other => panic!("expected a schema error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_tag_out_of_range() {
+        let json = r#"{
+          "frames": [
+            { "frame": { "x": 0, "y": 0, "w": 32, "h": 32 }, "duration": 1000 }
+          ],
+          "meta": {
+            "size": { "w": 32, "h": 32 },
+            "frameTags": [
+              { "name": "Alpha", "from": 0, "to": 1, "direction": "forward" }
+            ]
+          }
+        }"#;
+        match ExportData::parse_str(json) {
+            Err(Error::TagOutOfRange {
+                tag,
+                to,
+                frame_count,
+                ..
+            }) => {
+                assert_eq!(tag, "Alpha");
+                assert_eq!(to, 1);
+                assert_eq!(frame_count, 1);
+            }
+            other => panic!("expected a tag range error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_slice_pivots() {
+        let json = r##"{
+          "frames": [
+            { "frame": { "x": 0, "y": 0, "w": 32, "h": 32 }, "duration": 100 },
+            { "frame": { "x": 32, "y": 0, "w": 32, "h": 32 }, "duration": 100 },
+            { "frame": { "x": 64, "y": 0, "w": 32, "h": 32 }, "duration": 100 }
+          ],
+          "meta": {
+            "size": { "w": 96, "h": 32 },
+            "frameTags": [],
+            "slices": [
+              { "name": "Hitbox", "color": "#ff0000ff", "keys": [
+                { "frame": 0, "bounds": { "x": 0, "y": 0, "w": 32, "h": 32 } }
+              ]},
+              { "name": "Feet", "color": "#0000ffff", "keys": [
+                { "frame": 1, "bounds": { "x": 8, "y": 24, "w": 16, "h": 8 },
+                  "pivot": { "x": 8, "y": 8 } },
+                { "frame": 2, "bounds": { "x": 10, "y": 24, "w": 16, "h": 8 },
+                  "pivot": { "x": 8, "y": 7 } }
+              ]}
+            ]
+          }
+        }"##;
+        let data = ExportData::parse_str(json).unwrap();
+        assert_eq!(data.pivot(0), None);
+        assert_eq!(data.pivot(1), Some((16, 32)));
+        assert_eq!(data.pivot(2), Some((18, 31)));
+    }
 }
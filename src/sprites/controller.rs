@@ -0,0 +1,367 @@
+//! The `controller` module picks which clip a sprite plays, as a state machine over the tags in
+//! a `ClipStore`.
+//!
+//! An `AnimationGraph` names the states, the clip and `PlayMode` each one plays, and the
+//! transitions between them. It's plain JSON, so it can live alongside the sprite sheet.
+//!
+//! ```json
+//! {
+//!   "initial": "idle",
+//!   "states": {
+//!     "idle": { "clip": "Ready", "mode": "loop" },
+//!     "windup": { "clip": "Winding", "mode": "one_shot" },
+//!     "pitch": { "clip": "Pitching", "mode": "one_shot" }
+//!   },
+//!   "transitions": [
+//!     { "from": "idle", "to": "windup", "conditions": [{ "trigger": "pitch" }] },
+//!     { "from": "windup", "to": "pitch", "exit_time": true },
+//!     { "from": "pitch", "to": "idle", "exit_time": true }
+//!   ]
+//! }
+//! ```
+//!
+//! Conditions test the parameters set on an `AnimationController` by the game:
+//!
+//! - `{ "if": "name" }` and `{ "if_not": "name" }` test a bool.
+//! - `{ "trigger": "name" }` passes once `set_trigger("name")` has been called, and the trigger is
+//!   cleared when a transition using it is taken.
+//! - `{ "greater": ["name", 0.5] }` and `{ "less": ["name", 0.5] }` compare a float.
+//!
+//! Parameters which were never set read as `false` and `0`.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use serde_json;
+use errors::{Error, Result};
+use super::{AnimationClip, ClipEvent, ClipStore, Delta, PlayMode};
+
+/// A state in an `AnimationGraph`, which plays the clip for the tag named `clip`.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct State {
+    pub clip: String,
+    pub mode: PlayMode,
+}
+
+/// A test of one of an `AnimationController`'s parameters.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub enum Condition {
+    #[serde(rename = "if")]
+    If(String),
+    #[serde(rename = "if_not")]
+    IfNot(String),
+    #[serde(rename = "trigger")]
+    Trigger(String),
+    #[serde(rename = "greater")]
+    Greater(String, f32),
+    #[serde(rename = "less")]
+    Less(String, f32),
+}
+
+/// A move from one state to another, taken once all its conditions pass.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Transition {
+    pub from: String,
+    pub to: String,
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+    /// Wait for the state's clip to finish before moving on: for a `OneShot` or `Hold` clip,
+    /// until it reaches its end, and for a `Loop` clip, until it next wraps around. The
+    /// transition is taken at that moment, with the rest of the update played by the next clip.
+    #[serde(default)]
+    pub exit_time: bool,
+}
+
+/// `AnimationGraph` describes the states and transitions run by an `AnimationController`.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct AnimationGraph {
+    pub initial: String,
+    pub states: HashMap<String, State>,
+    /// When several transitions out of a state could be taken, the first listed wins.
+    #[serde(default)]
+    pub transitions: Vec<Transition>,
+}
+
+impl AnimationGraph {
+    pub fn parse_str(json: &str) -> Result<AnimationGraph> {
+        serde_json::from_str(json).map_err(|e| Error::from_json(e, None))
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<AnimationGraph> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|e| {
+            Error::Io {
+                path: Some(path.to_path_buf()),
+                cause: e,
+            }
+        })?;
+        Self::from_reader(file, Some(path))
+    }
+
+    /// Reads a graph from any `Read`, such as a file opened via ggez's filesystem. `path` is only
+    /// used to annotate errors.
+    pub fn from_reader<R: Read>(reader: R, path: Option<&Path>) -> Result<AnimationGraph> {
+        serde_json::from_reader(reader).map_err(|e| Error::from_json(e, path))
+    }
+}
+
+/// `AnimationController` runs an `AnimationGraph`, switching clips as its parameters change and
+/// its clips finish.
+#[derive(Debug, Clone)]
+pub struct AnimationController {
+    state: String,
+    clip: AnimationClip,
+    /// A fresh clip for each state, copied when the state is entered.
+    clips: HashMap<String, AnimationClip>,
+    transitions: Vec<Transition>,
+    bools: HashMap<String, bool>,
+    floats: HashMap<String, f32>,
+    triggers: HashSet<String>,
+}
+
+impl AnimationController {
+    /// Creates a controller in the graph's initial state, playing clips from `store`.
+    ///
+    /// Fails if the graph refers to a state it doesn't define, or a clip `store` doesn't have.
+    pub fn new(graph: &AnimationGraph, store: &ClipStore) -> Result<Self> {
+        let mut clips = HashMap::new();
+        for (name, state) in &graph.states {
+            let clip = store.create(&state.clip, state.mode.clone()).ok_or_else(|| {
+                Error::UnknownClip(state.clip.clone())
+            })?;
+            clips.insert(name.clone(), clip);
+        }
+        for transition in &graph.transitions {
+            for name in &[&transition.from, &transition.to] {
+                if !clips.contains_key(name.as_str()) {
+                    return Err(Error::UnknownState(name.to_string()));
+                }
+            }
+        }
+        let clip = clips.get(&graph.initial).cloned().ok_or_else(|| {
+            Error::UnknownState(graph.initial.clone())
+        })?;
+
+        Ok(AnimationController {
+            state: graph.initial.clone(),
+            clip: clip,
+            clips: clips,
+            transitions: graph.transitions.clone(),
+            bools: HashMap::new(),
+            floats: HashMap::new(),
+            triggers: HashSet::new(),
+        })
+    }
+
+    /// The name of the current state.
+    pub fn state(&self) -> &str {
+        &self.state
+    }
+
+    /// The clip being played for the current state.
+    pub fn clip(&self) -> &AnimationClip {
+        &self.clip
+    }
+
+    pub fn get_cell(&self) -> Option<usize> {
+        self.clip.get_cell()
+    }
+
+    pub fn set_bool(&mut self, name: &str, value: bool) {
+        self.bools.insert(name.to_string(), value);
+    }
+
+    pub fn set_float(&mut self, name: &str, value: f32) {
+        self.floats.insert(name.to_string(), value);
+    }
+
+    /// Sets a trigger, which stays set until a transition using it is taken.
+    pub fn set_trigger(&mut self, name: &str) {
+        self.triggers.insert(name.to_string());
+    }
+
+    pub fn reset_trigger(&mut self, name: &str) {
+        self.triggers.remove(name);
+    }
+
+    /// Advances the current state's clip by `dt`, moving between states along the way. Returns
+    /// the events reported by each clip played, in order.
+    ///
+    /// Transitions without `exit_time` are checked first, and at most one is taken. Exit time
+    /// transitions are then taken as each clip finishes, with the time left over played by the
+    /// next clip, so no time is lost between states. As with `ClipSequence`, the first cell of
+    /// each clip moved on to is reported as entered.
+    pub fn update(&mut self, dt: Delta) -> Vec<ClipEvent> {
+        let mut events = vec![];
+        if let Some(idx) = self.ready(false) {
+            self.enter(idx, &mut events);
+        }
+
+        let mut dt = dt;
+        while let Some((finishing, remaining)) = self.clip.finish(dt) {
+            let idx = match self.ready(true) {
+                Some(idx) => idx,
+                None => break,
+            };
+            events.extend(finishing);
+            self.enter(idx, &mut events);
+            dt = remaining;
+        }
+        events.extend(self.clip.update(dt));
+        events
+    }
+
+    /// The first transition out of the current state, with or without exit time, whose
+    /// conditions pass.
+    fn ready(&self, exit_time: bool) -> Option<usize> {
+        self.transitions.iter().position(|t| {
+            t.from == self.state && t.exit_time == exit_time &&
+                t.conditions.iter().all(|c| self.passes(c))
+        })
+    }
+
+    /// Takes the transition at `idx`, using up its triggers and starting the new state's clip.
+    fn enter(&mut self, idx: usize, events: &mut Vec<ClipEvent>) {
+        let transition = &self.transitions[idx];
+        for condition in &transition.conditions {
+            if let Condition::Trigger(ref name) = *condition {
+                self.triggers.remove(name);
+            }
+        }
+        self.state = transition.to.clone();
+        self.clip = self.clips[&transition.to].clone();
+        events.extend(self.clip.entered());
+    }
+
+    fn passes(&self, condition: &Condition) -> bool {
+        let float = |name: &str| self.floats.get(name).cloned().unwrap_or(0.);
+        match *condition {
+            Condition::If(ref name) => self.bools.get(name).cloned().unwrap_or(false),
+            Condition::IfNot(ref name) => !self.bools.get(name).cloned().unwrap_or(false),
+            Condition::Trigger(ref name) => self.triggers.contains(name),
+            Condition::Greater(ref name, value) => float(name) > value,
+            Condition::Less(ref name, value) => float(name) < value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sprites::test::get_pitcher_sheet;
+
+    fn get_pitcher_graph() -> AnimationGraph {
+        AnimationGraph::parse_str(
+            r#"{
+          "initial": "idle",
+          "states": {
+            "idle": { "clip": "Ready", "mode": "loop" },
+            "windup": { "clip": "Winding", "mode": "one_shot" },
+            "pitch": { "clip": "Pitching", "mode": "one_shot" },
+            "tired": { "clip": "Not Ready", "mode": "hold" }
+          },
+          "transitions": [
+            { "from": "idle", "to": "windup", "conditions": [{ "trigger": "pitch" }] },
+            { "from": "idle", "to": "tired", "conditions": [{ "greater": ["fatigue", 0.5] }] },
+            { "from": "windup", "to": "pitch", "exit_time": true },
+            {
+              "from": "pitch",
+              "to": "idle",
+              "exit_time": true,
+              "conditions": [{ "less": ["fatigue", 0.5] }]
+            },
+            { "from": "pitch", "to": "tired", "exit_time": true },
+            { "from": "tired", "to": "idle", "exit_time": true, "conditions": [{ "if": "rested" }] }
+          ]
+        }"#,
+        ).unwrap()
+    }
+
+    fn get_controller() -> AnimationController {
+        let store = ClipStore::new(&get_pitcher_sheet());
+        AnimationController::new(&get_pitcher_graph(), &store).unwrap()
+    }
+
+    #[test]
+    fn test_trigger_and_exit_time() {
+        let mut controller = get_controller();
+        controller.update(Delta::from_millis(50.));
+        assert_eq!(controller.state(), "idle");
+
+        controller.set_trigger("pitch");
+        assert_eq!(
+            controller.update(Delta::from_millis(50.)),
+            vec![ClipEvent::FrameEntered(8)]
+        );
+        assert_eq!(controller.state(), "windup");
+
+        // Winding lasts 2100ms, and isn't left until it's over
+        controller.update(Delta::from_millis(2000.));
+        assert_eq!(controller.state(), "windup");
+        assert_eq!(
+            controller.update(Delta::from_millis(50.)),
+            vec![ClipEvent::Finished, ClipEvent::FrameEntered(14)]
+        );
+        assert_eq!(controller.state(), "pitch");
+
+        // Pitching lasts 400ms, and what's left of the update is played by the next state
+        let events = controller.update(Delta::from_millis(450.));
+        assert_eq!(controller.state(), "idle");
+        assert_eq!(
+            events,
+            vec![
+                ClipEvent::FrameEntered(15),
+                ClipEvent::FrameEntered(16),
+                ClipEvent::FrameEntered(17),
+                ClipEvent::Finished,
+                ClipEvent::FrameEntered(0),
+            ]
+        );
+        assert_eq!(controller.clip().current_time, Delta::from_millis(50.));
+
+        // the trigger was used up
+        controller.update(Delta::from_millis(1000.));
+        assert_eq!(controller.state(), "idle");
+    }
+
+    #[test]
+    fn test_bool_and_float_conditions() {
+        let mut controller = get_controller();
+        controller.set_float("fatigue", 0.75);
+        controller.update(Delta::from_millis(50.));
+        assert_eq!(controller.state(), "tired");
+
+        controller.update(Delta::from_millis(1000.));
+        assert_eq!(controller.state(), "tired");
+        assert_eq!(controller.get_cell(), Some(20));
+
+        controller.set_bool("rested", true);
+        controller.update(Delta::zero());
+        assert_eq!(controller.state(), "idle");
+        assert_eq!(controller.get_cell(), Some(0));
+    }
+
+    #[test]
+    fn test_unknown_names() {
+        let store = ClipStore::new(&get_pitcher_sheet());
+
+        let mut graph = get_pitcher_graph();
+        graph.states.get_mut("pitch").unwrap().clip = "Throwing".to_string();
+        match AnimationController::new(&graph, &store) {
+            Err(Error::UnknownClip(name)) => assert_eq!(name, "Throwing"),
+            other => panic!("expected an unknown clip error, got {:?}", other),
+        }
+
+        let mut graph = get_pitcher_graph();
+        graph.transitions[0].to = "throw".to_string();
+        match AnimationController::new(&graph, &store) {
+            Err(Error::UnknownState(name)) => assert_eq!(name, "throw"),
+            other => panic!("expected an unknown state error, got {:?}", other),
+        }
+        assert_eq!(
+            AnimationController::new(&graph, &store).unwrap_err().to_string(),
+            "no animation state named `throw`"
+        );
+    }
+}
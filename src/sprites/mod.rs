@@ -2,7 +2,10 @@
 //! over time.
 
 use std::collections::hash_map::HashMap;
+pub use Delta;
 pub mod aseprite;
+pub mod controller;
+pub mod sequence;
 
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
@@ -17,17 +20,24 @@ pub struct Region {
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct Frame {
-    pub duration: i32,
+    pub duration: Delta,
     #[serde(rename = "frame")]
     pub bbox: Region,
 }
 
-#[derive(Debug, Clone)]
+/// The order a tag's frames are played in, as named in Aseprite's export data.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum Direction {
+    #[serde(rename = "forward")]
     Forward,
+    #[serde(rename = "reverse")]
     Reverse,
+    /// Forward, then back again.
+    #[serde(rename = "pingpong")]
     PingPong,
-    Unknown,
+    /// Backwards, then forward again. Added in Aseprite 1.3.
+    #[serde(rename = "pingpong_reverse")]
+    PingPongReverse,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
@@ -35,33 +45,51 @@ pub struct FrameTag {
     pub name: String,
     pub from: usize,
     pub to: usize,
-    // one of "forward", "reverse", "pingpong"
-    pub direction: String,
+    pub direction: Direction,
+    /// The tag's user data, exported by Aseprite 1.3 or later.
+    #[serde(default)]
+    pub data: Option<String>,
 }
 
-pub type Delta = f32;
-pub type FrameDuration = i32;
-
 /// `CellInfo.idx` points to an index in `SpriteSheetData.cells` and `CellInfo.duration` indicates
 /// how long this section of the texture atlas should be displayed as per an `AnimationClip`.
 #[derive(Debug, Clone)]
 pub struct CellInfo {
     pub idx: usize,
-    pub duration: FrameDuration,
+    pub duration: Delta,
+    /// Reported as `ClipEvent::Marker`s when the cell is entered. `ClipStore` fills these in from
+    /// the user data of the frame's cels, and of the tag on the first cell played.
+    pub markers: Vec<String>,
+}
+
+/// Something which happened while an `AnimationClip` was updated.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClipEvent {
+    /// The clip moved on to a new cell, whose sprite sheet index is given.
+    FrameEntered(usize),
+    /// A `Loop` clip wrapped around to its start (or, playing backwards, its end).
+    LoopCompleted,
+    /// A `OneShot` or `Hold` clip ran past its end (or, playing backwards, its start).
+    Finished,
+    /// A cell with this marker was entered. Follows the `FrameEntered` for the cell.
+    Marker(String),
 }
 
 
 /// `PlayMode` controls how the current frame data for a clip at a certain time is calculated with
 /// regards to the duration bounds.
-#[derive(PartialEq, Debug, Clone)]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub enum PlayMode {
     /// `OneShot` will play start to finish, but requests for `CellInfo` after the duration will get
     /// you None.
+    #[serde(rename = "one_shot")]
     OneShot,
     /// `Hold` is similar to `OneShot` however time past the end of the duration will repeat
     /// the final frame.
+    #[serde(rename = "hold")]
     Hold,
     /// A `Loop` clip never ends and will return to the start of the clip when exhausted.
+    #[serde(rename = "loop")]
     Loop,
 }
 
@@ -74,24 +102,31 @@ pub struct AnimationClipTemplate {
 }
 
 impl AnimationClipTemplate {
+    /// Builds the cells for one cycle of a looping clip over `frames`, which start at `offset` in
+    /// the sprite sheet.
+    ///
+    /// The ping-pong directions show the first and last frames once per bounce, so frames 0 to 3
+    /// cycle as 0 1 2 3 2 1 (or 3 2 1 0 1 2 in reverse). Clips which don't loop play only the
+    /// first pass of that cycle; see `AnimationClip::new`.
     pub fn new(name: String, frames: &[Frame], direction: Direction, offset: usize) -> Self {
+        let cell = |idx: usize| {
+            CellInfo {
+                idx: offset + idx,
+                duration: frames[idx].duration,
+                markers: vec![],
+            }
+        };
+        // the frames between the first and last, which are shown on the way back
+        let inner = if frames.len() > 2 { 1..frames.len() - 1 } else { 0..0 };
         let cell_info: Vec<CellInfo> = match direction {
-            Direction::Reverse =>
-                frames.iter().enumerate().rev()
-                    .map(|(idx, x)| CellInfo { idx: offset + idx, duration: x.duration})
-                    .collect(),
-            // Look at what aseprite does about each end (double frame problem)
-            Direction::PingPong =>
-                frames.iter().enumerate().chain(frames.iter().enumerate().rev())
-                    .map(|(idx, x)| CellInfo { idx: offset + idx, duration: x.duration})
-                    .collect(),
-            _ =>  // assumes Forward in the fallback case
-                frames.iter().enumerate()
-                    .map(|(idx, x)| CellInfo { idx: offset + idx, duration: x.duration})
-                    .collect()
-
+            Direction::Forward => (0..frames.len()).map(cell).collect(),
+            Direction::Reverse => (0..frames.len()).rev().map(cell).collect(),
+            Direction::PingPong => (0..frames.len()).chain(inner.rev()).map(cell).collect(),
+            Direction::PingPongReverse => {
+                (0..frames.len()).rev().chain(inner).map(cell).collect()
+            }
         };
-        let duration = cell_info.iter().map(|x| x.duration as Delta).sum();
+        let duration = cell_info.iter().map(|x| x.duration).sum();
         Self {
             name: name,
             cells: cell_info,
@@ -114,22 +149,28 @@ impl AnimationClipTemplate {
 /// use omn_labs::sprites::{AnimationClip, CellInfo, Delta, Frame, Region, Direction, PlayMode};
 ///
 /// let frames = vec![
-///     Frame { duration: 1000, bbox: Region { x: 0, y: 0, width: 32, height: 32 } },
-///     Frame { duration: 1000, bbox: Region { x: 32, y: 0, width: 32, height: 32 } },
+///     Frame {
+///         duration: Delta::from_millis(1000.),
+///         bbox: Region { x: 0, y: 0, width: 32, height: 32 },
+///     },
+///     Frame {
+///         duration: Delta::from_millis(1000.),
+///         bbox: Region { x: 32, y: 0, width: 32, height: 32 },
+///     },
 /// ];
 ///
 /// let mut clip =
 ///   AnimationClip::from_frames("Two Frames", Direction::Forward, PlayMode::Loop, &frames);
 ///
 /// assert_eq!(clip.get_cell(), Some(0));
-/// clip.update(800.);
+/// clip.update(Delta::from_millis(800.));
 ///
 /// assert_eq!(clip.get_cell(), Some(0));
-/// clip.update(800.);
+/// clip.update(Delta::from_millis(800.));
 ///
 /// // as playback progresses, we get different frames as a return
 /// assert_eq!(clip.get_cell(), Some(1));
-/// clip.update(800.);
+/// clip.update(Delta::from_millis(800.));
 ///
 /// // and as the "play head" extends beyond the total duration of the clip, it'll loop back
 /// // around to the start. This wrapping behaviour can be customized via the `Direction` parameter.
@@ -141,23 +182,49 @@ pub struct AnimationClip {
     pub current_time: Delta, // represents the "play head"
     pub direction: Direction,
     pub duration: Delta,
+    /// How fast the clip plays, as a multiple of its normal speed. 0 pauses the clip, and negative
+    /// rates play it backwards.
+    pub rate: f32,
     cells: Vec<CellInfo>,
     mode: PlayMode,
     pub drained: bool,
+    /// Whether the clip was drained by playing backwards past its start, rather than forwards
+    /// past its end.
+    drained_at_start: bool,
+    /// Whether the cell showing when the clip was created has been reported as entered.
+    started: bool,
 }
 
 
 impl AnimationClip {
+    /// Creates a clip playing `template` in `play_mode`.
+    ///
+    /// A ping-pong clip which doesn't loop plays a single pass, ending on the last frame (the
+    /// first in reverse) rather than bouncing back. This follows Aseprite, which counts each
+    /// direction of a ping-pong tag as one repeat, so a tag set to repeat once plays 0 1 2 3.
     pub fn new(template: &AnimationClipTemplate, play_mode: PlayMode) -> Self {
-
+        let mut cells = template.cells.clone();
+        let single_pass = match template.direction {
+            Direction::PingPong | Direction::PingPongReverse => play_mode != PlayMode::Loop,
+            _ => false,
+        };
+        if single_pass && cells.len() > 1 {
+            // the cycle is one pass out and the inner frames back
+            let pass = cells.len() / 2 + 1;
+            cells.truncate(pass);
+        }
+        let duration = cells.iter().map(|x| x.duration).sum();
         AnimationClip {
             name: template.name.to_owned(),
-            current_time: 0.,
+            current_time: Delta::zero(),
             direction: template.direction.clone(),
-            duration: template.duration,
-            cells: template.cells.clone(),
+            duration: duration,
+            rate: 1.,
+            cells: cells,
             mode: play_mode,
             drained: false,
+            drained_at_start: false,
+            started: false,
         }
     }
 
@@ -167,45 +234,171 @@ impl AnimationClip {
         play_mode: PlayMode,
         frames: &[Frame],
     ) -> Self {
-        AnimationClip {
-            name: name.to_string(),
-            cells: frames
-                .iter()
-                .enumerate()
-                .map(|(idx, x)| {
-                    CellInfo {
-                        idx: idx,
-                        duration: x.duration,
-                    }
-                })
-                .collect(),
-            current_time: 0.,
-            duration: frames.iter().map(|x| x.duration as Delta).sum(),
-            direction: direction,
-            mode: play_mode,
-            drained: false,
-        }
+        let template = AnimationClipTemplate::new(name.to_string(), frames, direction, 0);
+        AnimationClip::new(&template, play_mode)
     }
 
-    pub fn update(&mut self, dt: Delta) {
-        let updated = self.current_time + dt;
+    /// Advances the play head by `dt`, scaled by the clip's `rate`, returning what happened along
+    /// the way in order.
+    ///
+    /// The cell showing when a clip is created is reported as entered (along with its markers,
+    /// such as the tag's user data) by the first update which moves the play head. Every cell
+    /// passed through is reported, however many cycles a single update covers.
+    pub fn update(&mut self, dt: Delta) -> Vec<ClipEvent> {
+        let step = dt * self.rate;
+        let mut events = self.start(step);
+        events.extend(self.events(self.current_time, step));
+        if step != Delta::zero() {
+            self.started = true;
+        }
+        let updated = self.current_time + step;
 
         self.current_time = if updated > self.duration {
             self.drained = match self.mode {
                 PlayMode::OneShot | PlayMode::Hold => true,
                 _ => false,
             };
+            self.drained_at_start = false;
 
             updated % self.duration
+        } else if updated < Delta::zero() {
+            self.drained = self.mode != PlayMode::Loop;
+            self.drained_at_start = self.drained;
+
+            // wrap around to the end, as `%` keeps the sign of `updated`
+            updated % self.duration + self.duration
         } else {
             updated
         };
+        events
+    }
+
+    /// The events of a clip which hasn't started yet being moved by `by`: entering the cell it
+    /// was showing. A clip played backwards from its very start skips that cell, as it goes
+    /// straight round to its last (which `events()` reports).
+    fn start(&self, by: Delta) -> Vec<ClipEvent> {
+        if self.started || self.drained || self.duration <= Delta::zero() || by == Delta::zero() {
+            return vec![];
+        }
+        if by < Delta::zero() && self.current_time <= Delta::zero() {
+            return vec![];
+        }
+        let (pos, _) = self.position(self.current_time);
+        entering(&self.cells[pos])
+    }
+
+    /// Finds the cell showing at `time` and when it started. The first cell covers its start and
+    /// end times, and the rest only their end times, matching `get_cell()`.
+    fn position(&self, time: Delta) -> (usize, Delta) {
+        let mut pos = 0;
+        let mut start = Delta::zero();
+        while pos + 1 < self.cells.len() && time > start + self.cells[pos].duration {
+            start += self.cells[pos].duration;
+            pos += 1;
+        }
+        (pos, start)
+    }
+
+    /// Walks the cells passed through when moving the play head from `from` by `by`.
+    fn events(&self, from: Delta, by: Delta) -> Vec<ClipEvent> {
+        let mut events = vec![];
+        // A clip with no length would never get anywhere.
+        if self.drained || self.duration <= Delta::zero() || by == Delta::zero() {
+            return events;
+        }
+
+        let (mut pos, mut start) = self.position(from);
+        let target = from + by;
+        let last = self.cells.len() - 1;
+        loop {
+            if by > Delta::zero() {
+                let end = start + self.cells[pos].duration;
+                if target <= end {
+                    break;
+                }
+                start = end;
+                if pos == last {
+                    if self.mode != PlayMode::Loop {
+                        events.push(ClipEvent::Finished);
+                        break;
+                    }
+                    events.push(ClipEvent::LoopCompleted);
+                    pos = 0;
+                } else {
+                    pos += 1;
+                }
+            } else {
+                if target > start || (pos == 0 && target == start) {
+                    break;
+                }
+                if pos == 0 {
+                    if self.mode != PlayMode::Loop {
+                        events.push(ClipEvent::Finished);
+                        break;
+                    }
+                    events.push(ClipEvent::LoopCompleted);
+                    pos = last;
+                } else {
+                    pos -= 1;
+                }
+                start -= self.cells[pos].duration;
+            }
+            events.extend(entering(&self.cells[pos]));
+        }
+        events
+    }
+
+    /// If advancing by `dt` would take the clip to the end of its current cycle (or past it),
+    /// returns its events up to and including that end, and the part of `dt` left over. A clip
+    /// which has already drained ends straight away, leaving all of `dt`.
+    ///
+    /// Used to hand over from one clip to the next without losing time.
+    fn finish(&self, dt: Delta) -> Option<(Vec<ClipEvent>, Delta)> {
+        if self.drained {
+            return Some((vec![], dt));
+        }
+        let step = dt * self.rate;
+        let end = self.current_time + step;
+        let past = if step > Delta::zero() && end >= self.duration {
+            end - self.duration
+        } else if step < Delta::zero() && end <= Delta::zero() {
+            -end
+        } else {
+            return None;
+        };
+
+        let mut events = self.start(step);
+        events.extend(self.events(self.current_time, step));
+        let cycle_end = events.iter().position(|e| match *e {
+            ClipEvent::LoopCompleted | ClipEvent::Finished => true,
+            _ => false,
+        });
+        match cycle_end {
+            Some(idx) => events.truncate(idx + 1),
+            // landing exactly on the end isn't reported by `events()`
+            None if self.mode == PlayMode::Loop => events.push(ClipEvent::LoopCompleted),
+            None => events.push(ClipEvent::Finished),
+        }
+        Some((events, dt * (past / step.abs())))
+    }
+
+    /// The events of the clip starting to play: entering its first cell (or its last, if it
+    /// plays backwards). The clip counts as started, so its first update won't report them again.
+    fn entered(&mut self) -> Vec<ClipEvent> {
+        self.started = true;
+        let cell = if self.rate < 0. {
+            self.cells.last()
+        } else {
+            self.cells.first()
+        };
+        cell.map(entering).unwrap_or_default()
     }
 
     /// Explicitly sets the current time of the clip and adjusts the internal
     /// `AnimationClip.drained` value based on the clip's mode and whether the new time is larger
     /// than the duration.
     pub fn set_time(&mut self, time: Delta) {
+        self.drained_at_start = false;
         self.current_time = if time > self.duration {
             self.drained = self.mode != PlayMode::Loop;
             time % self.duration
@@ -217,7 +410,7 @@ impl AnimationClip {
 
     /// Put the play head back to the start of the clip.
     pub fn reset(&mut self) {
-        self.set_time(0.);
+        self.set_time(Delta::zero());
     }
 
     /// Returns the cell index for the current time of the clip or None if the clip is over.
@@ -226,6 +419,8 @@ impl AnimationClip {
         if self.drained {
             return if self.mode == PlayMode::OneShot {
                 None
+            } else if self.drained_at_start {
+                Some(self.cells[0].idx)
             } else {
                 Some(self.cells.last().unwrap().idx)
             };
@@ -237,15 +432,15 @@ impl AnimationClip {
             // FIXME: dupe code caused by iter() and cycle() having different types (otherwise
             // would return a generic iter from match and loop over after).
             for cell in self.cells.iter().cycle() {
-                remaining_time -= cell.duration as Delta;
-                if remaining_time <= 0. {
+                remaining_time -= cell.duration;
+                if remaining_time <= Delta::zero() {
                     return Some(cell.idx);
                 }
             }
         } else {
             for cell in self.cells.iter() {
-                remaining_time -= cell.duration as Delta;
-                if remaining_time <= 0. {
+                remaining_time -= cell.duration;
+                if remaining_time <= Delta::zero() {
                     return Some(cell.idx);
                 }
             }
@@ -259,6 +454,13 @@ impl AnimationClip {
     }
 }
 
+/// The events of playback moving on to `cell`.
+fn entering(cell: &CellInfo) -> Vec<ClipEvent> {
+    let mut events = vec![ClipEvent::FrameEntered(cell.idx)];
+    events.extend(cell.markers.iter().cloned().map(ClipEvent::Marker));
+    events
+}
+
 #[derive(Debug)]
 pub struct ClipStore {
     store: HashMap<String, AnimationClipTemplate>,
@@ -274,18 +476,23 @@ impl ClipStore {
                 let mut clips = HashMap::new();
 
                 for tag in &data.meta.frame_tags {
-
-                    let direction = match tag.direction.as_ref() {
-                        "forward" => Direction::Forward,
-                        "reverse" => Direction::Reverse,
-                        "pingpong" => Direction::PingPong,
-                        _ => Direction::Unknown,
-                    };
                     let frames: &[Frame] = &data.frames[tag.from..tag.to + 1];
-                    clips.insert(
+                    let mut template = AnimationClipTemplate::new(
                         tag.name.clone(),
-                        AnimationClipTemplate::new(tag.name.clone(), frames, direction, tag.from),
+                        frames,
+                        tag.direction.clone(),
+                        tag.from,
                     );
+                    for (i, cell) in template.cells.iter_mut().enumerate() {
+                        // the first cell played, which is the tag's last frame when reversed
+                        if i == 0 {
+                            let tag_data = tag.data.iter().filter(|x| !x.is_empty());
+                            cell.markers.extend(tag_data.cloned());
+                        }
+                        let cel_data = data.markers(cell.idx).into_iter().map(String::from);
+                        cell.markers.extend(cel_data);
+                    }
+                    clips.insert(tag.name.clone(), template);
                 }
 
                 clips
@@ -296,6 +503,27 @@ impl ClipStore {
     pub fn create(&self, key: &str, mode: PlayMode) -> Option<AnimationClip> {
         self.store.get(key).map(|x| AnimationClip::new(x, mode))
     }
+
+    /// Rebuilds `clip` from this store's template of the same name, keeping its `PlayMode` and
+    /// play head. This is useful after the sheet data has been reloaded, since the frames and
+    /// durations of the tag may have changed.
+    ///
+    /// Returns `false` (leaving `clip` untouched) if the store has no template for the clip's name.
+    pub fn refresh(&self, clip: &mut AnimationClip) -> bool {
+        match self.store.get(&clip.name) {
+            Some(template) => {
+                let mut fresh = AnimationClip::new(template, clip.mode.clone());
+                fresh.rate = clip.rate;
+                fresh.set_time(clip.current_time);
+                fresh.drained = fresh.drained || clip.drained;
+                fresh.drained_at_start = clip.drained_at_start;
+                fresh.started = clip.started;
+                *clip = fresh;
+                true
+            }
+            None => false,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -304,7 +532,8 @@ mod test {
 
     #[test]
     fn test_read_from_file() {
-        let sheet = SpriteSheetData::from_file("resources/numbers/numbers-matrix-tags.array.json");
+        let sheet = SpriteSheetData::from_file("resources/numbers/numbers-matrix-tags.array.json")
+            .unwrap();
         let clips = ClipStore::new(&sheet);
 
         let alpha = clips.create("Alpha", PlayMode::Loop).unwrap();
@@ -317,7 +546,8 @@ mod test {
 
     #[test]
     fn test_clips_are_distinct() {
-        let sheet = SpriteSheetData::from_file("resources/numbers/numbers-matrix-tags.array.json");
+        let sheet = SpriteSheetData::from_file("resources/numbers/numbers-matrix-tags.array.json")
+            .unwrap();
         let clips = ClipStore::new(&sheet);
 
 
@@ -326,8 +556,8 @@ mod test {
         let mut alpha1 = clips.create("Alpha", PlayMode::Loop).unwrap();
         let mut alpha2 = clips.create("Alpha", PlayMode::Loop).unwrap();
 
-        alpha1.update(20.);
-        alpha2.update(120.);
+        alpha1.update(Delta::from_millis(20.));
+        alpha2.update(Delta::from_millis(120.));
 
         assert_eq!(alpha1.get_cell(), Some(0));
         assert_eq!(alpha2.get_cell(), Some(1));
@@ -348,7 +578,7 @@ mod test {
         let clips = ClipStore::new(&sheet);
 
         let alpha1 = clips.create("Alpha", PlayMode::Loop).unwrap();
-        assert!((alpha1.duration - 30.).abs() < 0.1);
+        assert!((alpha1.duration.as_millis() - 30.).abs() < 0.1);
     }
 
     #[test]
@@ -361,20 +591,20 @@ mod test {
 
         assert_eq!(alpha1.get_cell(), Some(0));
 
-        alpha1.update(10.);
+        alpha1.update(Delta::from_millis(10.));
         assert_eq!(alpha1.get_cell(), Some(0));
 
-        alpha1.update(1.);
+        alpha1.update(Delta::from_millis(1.));
         assert_eq!(alpha1.get_cell(), Some(1));
 
-        alpha1.update(19.);
+        alpha1.update(Delta::from_millis(19.));
         assert_eq!(alpha1.get_cell(), Some(1));
 
         // we should be at the end of the clip at this point
-        assert!((alpha1.current_time - alpha1.duration).abs() < 0.1);
+        assert!((alpha1.current_time - alpha1.duration).abs() < Delta::from_millis(0.1));
 
 
-        alpha1.update(1.);
+        alpha1.update(Delta::from_millis(1.));
         assert_eq!(alpha1.get_cell(), None);
 
     }
@@ -389,20 +619,20 @@ mod test {
 
         assert_eq!(alpha1.get_cell(), Some(0));
 
-        alpha1.update(10.);
+        alpha1.update(Delta::from_millis(10.));
         assert_eq!(alpha1.get_cell(), Some(0));
 
-        alpha1.update(1.);
+        alpha1.update(Delta::from_millis(1.));
         assert_eq!(alpha1.get_cell(), Some(1));
 
-        alpha1.update(19.);
+        alpha1.update(Delta::from_millis(19.));
         assert_eq!(alpha1.get_cell(), Some(1));
 
         // we should be at the end of the clip at this point
-        assert!((alpha1.current_time - alpha1.duration).abs() < 0.1);
+        assert!((alpha1.current_time - alpha1.duration).abs() < Delta::from_millis(0.1));
         assert_eq!(alpha1.drained, false);
 
-        alpha1.update(1.);
+        alpha1.update(Delta::from_millis(1.));
         assert_eq!(alpha1.drained, true);
 
         assert_eq!(alpha1.get_cell(), Some(1));
@@ -417,21 +647,322 @@ mod test {
 
         let mut not_ready = clips.create("Not Ready", PlayMode::OneShot).unwrap();
 
-        not_ready.update(100.);
+        not_ready.update(Delta::from_millis(100.));
         assert_eq!(not_ready.get_cell(), Some(18));
-        not_ready.update(100.);
+        not_ready.update(Delta::from_millis(100.));
         assert_eq!(not_ready.get_cell(), Some(19));
-        not_ready.update(100.);
+        not_ready.update(Delta::from_millis(100.));
         assert_eq!(not_ready.get_cell(), Some(20));
-        not_ready.update(100.);
+        not_ready.update(Delta::from_millis(100.));
         assert_eq!(not_ready.get_cell(), None);
 
         //        let mut pitching = clips.create("Pitching", PlayMode::OneShot);
 
     }
 
+    #[test]
+    fn test_rate_scales_playback() {
+        let clips = ClipStore::new(&get_two_sheet());
+        let mut alpha = clips.create("Alpha", PlayMode::Loop).unwrap();
+
+        alpha.rate = 2.;
+        alpha.update(Delta::from_millis(6.));
+        assert_eq!(alpha.get_cell(), Some(1));
+
+        alpha.rate = 0.;
+        alpha.update(Delta::from_millis(100.));
+        assert!((alpha.current_time.as_millis() - 12.).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_negative_rate_loops_backwards() {
+        let clips = ClipStore::new(&get_two_sheet());
+        let mut alpha = clips.create("Alpha", PlayMode::Loop).unwrap();
+        alpha.rate = -1.;
+
+        alpha.update(Delta::from_millis(5.));
+        assert!((alpha.current_time.as_millis() - 25.).abs() < 0.1);
+        assert_eq!(alpha.get_cell(), Some(1));
+
+        alpha.update(Delta::from_millis(20.));
+        assert_eq!(alpha.get_cell(), Some(0));
+        assert!(!alpha.drained);
+    }
+
+    #[test]
+    fn test_negative_rate_drains_at_start() {
+        let clips = ClipStore::new(&get_two_sheet());
+
+        let mut one_shot = clips.create("Alpha", PlayMode::OneShot).unwrap();
+        one_shot.set_time(Delta::from_millis(15.));
+        one_shot.rate = -1.;
+        one_shot.update(Delta::from_millis(10.));
+        assert_eq!(one_shot.get_cell(), Some(0));
+        one_shot.update(Delta::from_millis(10.));
+        assert_eq!(one_shot.get_cell(), None);
+
+        let mut hold = clips.create("Alpha", PlayMode::Hold).unwrap();
+        hold.set_time(Delta::from_millis(15.));
+        hold.rate = -1.;
+        hold.update(Delta::from_millis(20.));
+        assert!(hold.drained);
+        assert_eq!(hold.get_cell(), Some(0));
+    }
+
+    #[test]
+    fn test_update_events() {
+        let clips = ClipStore::new(&get_two_sheet());
+
+        let mut looping = clips.create("Alpha", PlayMode::Loop).unwrap();
+        assert_eq!(
+            looping.update(Delta::from_millis(5.)),
+            vec![ClipEvent::FrameEntered(0)]
+        );
+        assert_eq!(
+            looping.update(Delta::from_millis(10.)),
+            vec![ClipEvent::FrameEntered(1)]
+        );
+        assert_eq!(
+            looping.update(Delta::from_millis(20.)),
+            vec![ClipEvent::LoopCompleted, ClipEvent::FrameEntered(0)]
+        );
+
+        // long updates report every cycle they cover
+        let events = looping.update(Delta::from_millis(300.));
+        let loops = events.iter().filter(|e| **e == ClipEvent::LoopCompleted);
+        assert_eq!(loops.count(), 10);
+        assert_eq!(events.len(), 30);
+
+        let mut one_shot = clips.create("Alpha", PlayMode::OneShot).unwrap();
+        assert_eq!(
+            one_shot.update(Delta::from_millis(31.)),
+            vec![
+                ClipEvent::FrameEntered(0),
+                ClipEvent::FrameEntered(1),
+                ClipEvent::Finished,
+            ]
+        );
+        assert_eq!(one_shot.update(Delta::from_millis(10.)), vec![]);
+
+        // played backwards from the start, a clip goes straight round to its last cell
+        let mut backwards = clips.create("Alpha", PlayMode::Loop).unwrap();
+        backwards.rate = -1.;
+        assert_eq!(
+            backwards.update(Delta::from_millis(5.)),
+            vec![ClipEvent::LoopCompleted, ClipEvent::FrameEntered(1)]
+        );
+    }
+
+    #[test]
+    fn test_markers_from_user_data() {
+        let sheet = aseprite::ExportData::parse_str(
+            r#"{
+          "frames": [
+            { "frame": { "x": 0, "y": 0, "w": 32, "h": 32 }, "duration": 10 },
+            { "frame": { "x": 32, "y": 0, "w": 32, "h": 32 }, "duration": 10 },
+            { "frame": { "x": 64, "y": 0, "w": 32, "h": 32 }, "duration": 10 }
+          ],
+          "meta": {
+            "size": { "w": 96, "h": 32 },
+            "frameTags": [
+              { "name": "Walk", "from": 0, "to": 2, "direction": "forward", "data": "step-cycle" },
+              { "name": "Back", "from": 0, "to": 2, "direction": "reverse", "data": "back-cycle" }
+            ],
+            "layers": [
+              { "name": "Body", "opacity": 255, "blendMode": "normal" },
+              { "name": "Feet", "opacity": 255, "blendMode": "normal", "cels": [
+                { "frame": 1, "data": "footstep" },
+                { "frame": 2, "data": "" }
+              ]}
+            ]
+          }
+        }"#,
+        ).unwrap();
+        let clips = ClipStore::new(&sheet);
+        let mut walk = clips.create("Walk", PlayMode::Loop).unwrap();
+
+        assert_eq!(
+            walk.update(Delta::from_millis(15.)),
+            vec![
+                ClipEvent::FrameEntered(0),
+                ClipEvent::Marker("step-cycle".to_string()),
+                ClipEvent::FrameEntered(1),
+                ClipEvent::Marker("footstep".to_string()),
+            ]
+        );
+        assert_eq!(
+            walk.update(Delta::from_millis(20.)),
+            vec![
+                ClipEvent::FrameEntered(2),
+                ClipEvent::LoopCompleted,
+                ClipEvent::FrameEntered(0),
+                ClipEvent::Marker("step-cycle".to_string()),
+            ]
+        );
+
+        // the tag's data marks the start of the clip, whichever way it plays
+        let mut back = clips.create("Back", PlayMode::Loop).unwrap();
+        assert_eq!(
+            back.update(Delta::from_millis(35.)),
+            vec![
+                ClipEvent::FrameEntered(2),
+                ClipEvent::Marker("back-cycle".to_string()),
+                ClipEvent::FrameEntered(1),
+                ClipEvent::Marker("footstep".to_string()),
+                ClipEvent::FrameEntered(0),
+                ClipEvent::LoopCompleted,
+                ClipEvent::FrameEntered(2),
+                ClipEvent::Marker("back-cycle".to_string()),
+            ]
+        );
+
+        // a clip which doesn't loop only marks its start as it begins
+        let mut once = clips.create("Walk", PlayMode::OneShot).unwrap();
+        assert_eq!(
+            once.update(Delta::from_millis(5.)),
+            vec![
+                ClipEvent::FrameEntered(0),
+                ClipEvent::Marker("step-cycle".to_string()),
+            ]
+        );
+        assert_eq!(
+            once.update(Delta::from_millis(30.)),
+            vec![
+                ClipEvent::FrameEntered(1),
+                ClipEvent::Marker("footstep".to_string()),
+                ClipEvent::FrameEntered(2),
+                ClipEvent::Finished,
+            ]
+        );
+    }
+
+    /// Plays a clip over `count` frames of 10ms each, noting the cell shown midway through each
+    /// 10ms of playback.
+    fn played(
+        direction: Direction,
+        mode: PlayMode,
+        count: usize,
+        samples: usize,
+    ) -> Vec<Option<usize>> {
+        let frames: Vec<Frame> = (0..count)
+            .map(|i| {
+                Frame {
+                    duration: Delta::from_millis(10.),
+                    bbox: Region {
+                        x: i as i32 * 32,
+                        y: 0,
+                        width: 32,
+                        height: 32,
+                    },
+                }
+            })
+            .collect();
+        let mut clip = AnimationClip::from_frames("Bounce", direction, mode, &frames);
+        clip.update(Delta::from_millis(5.));
+        (0..samples)
+            .map(|_| {
+                let cell = clip.get_cell();
+                clip.update(Delta::from_millis(10.));
+                cell
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_ping_pong_loop() {
+        assert_eq!(
+            played(Direction::PingPong, PlayMode::Loop, 4, 12),
+            vec![0, 1, 2, 3, 2, 1, 0, 1, 2, 3, 2, 1]
+                .into_iter()
+                .map(Some)
+                .collect::<Vec<Option<usize>>>()
+        );
+    }
+
+    #[test]
+    fn test_ping_pong_once_plays_one_pass() {
+        // Aseprite counts each direction as a repeat, so a ping-pong tag repeated once ends on
+        // its last frame
+        assert_eq!(
+            played(Direction::PingPong, PlayMode::OneShot, 4, 5),
+            vec![Some(0), Some(1), Some(2), Some(3), None]
+        );
+        assert_eq!(
+            played(Direction::PingPong, PlayMode::Hold, 4, 6),
+            vec![Some(0), Some(1), Some(2), Some(3), Some(3), Some(3)]
+        );
+    }
+
+    #[test]
+    fn test_ping_pong_reverse() {
+        assert_eq!(
+            played(Direction::PingPongReverse, PlayMode::Loop, 4, 8),
+            vec![Some(3), Some(2), Some(1), Some(0), Some(1), Some(2), Some(3), Some(2)]
+        );
+        assert_eq!(
+            played(Direction::PingPongReverse, PlayMode::OneShot, 4, 5),
+            vec![Some(3), Some(2), Some(1), Some(0), None]
+        );
+    }
+
+    #[test]
+    fn test_ping_pong_short_tags() {
+        assert_eq!(
+            played(Direction::PingPong, PlayMode::Loop, 2, 4),
+            vec![Some(0), Some(1), Some(0), Some(1)]
+        );
+        assert_eq!(
+            played(Direction::PingPong, PlayMode::OneShot, 2, 3),
+            vec![Some(0), Some(1), None]
+        );
+        assert_eq!(
+            played(Direction::PingPong, PlayMode::Loop, 1, 3),
+            vec![Some(0), Some(0), Some(0)]
+        );
+        assert_eq!(
+            played(Direction::PingPong, PlayMode::OneShot, 1, 2),
+            vec![Some(0), None]
+        );
+    }
+
+    #[test]
+    fn test_refresh_keeps_play_head() {
+        let clips = ClipStore::new(&get_two_sheet());
+        let mut alpha = clips.create("Alpha", PlayMode::Loop).unwrap();
+        alpha.update(Delta::from_millis(15.));
+
+        // the tag now has a third frame
+        let sheet = aseprite::ExportData::parse_str(
+            r#"{
+          "frames": [
+            { "frame": { "x": 0, "y": 0, "w": 32, "h": 32 }, "duration": 10 },
+            { "frame": { "x": 32, "y": 0, "w": 32, "h": 32 }, "duration": 20 },
+            { "frame": { "x": 64, "y": 0, "w": 32, "h": 32 }, "duration": 20 }
+          ],
+          "meta": {
+            "size": { "w": 96, "h": 32 },
+            "frameTags": [
+              { "name": "Alpha", "from": 0, "to": 2, "direction": "forward" }
+            ]
+          }
+        }"#,
+        ).unwrap();
+        let reloaded = ClipStore::new(&sheet);
+
+        assert!(reloaded.refresh(&mut alpha));
+        assert_eq!(alpha.cells.len(), 3);
+        assert!((alpha.current_time.as_millis() - 15.).abs() < 0.1);
+        assert_eq!(alpha.get_cell(), Some(1));
+
+        let mut beta = ClipStore::new(&get_pitcher_sheet())
+            .create("Ready", PlayMode::Loop)
+            .unwrap();
+        assert!(!reloaded.refresh(&mut beta));
+        assert_eq!(beta.name, "Ready");
+    }
+
     /// Generates a new sprite sheet with a 2 frame clip.
-    fn get_two_sheet() -> SpriteSheetData {
+    pub fn get_two_sheet() -> SpriteSheetData {
         aseprite::ExportData::parse_str(
             r#"{
           "frames": [
@@ -451,10 +982,10 @@ mod test {
             ]
           }
         }"#,
-        )
+        ).unwrap()
     }
     /// a real-world usage from LD38
-    fn get_pitcher_sheet() -> SpriteSheetData {
+    pub fn get_pitcher_sheet() -> SpriteSheetData {
         aseprite::ExportData::parse_str(
             r#"{
             "frames": [
@@ -490,6 +1021,6 @@ mod test {
                 ]
               }
             }"#,
-        )
+        ).unwrap()
     }
 }
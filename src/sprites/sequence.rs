@@ -0,0 +1,183 @@
+//! The `sequence` module chains clips together, such as a `OneShot` wind up followed by a looping
+//! idle.
+
+use std::collections::VecDeque;
+use super::{AnimationClip, ClipEvent, ClipStore, Delta, PlayMode};
+
+/// `ClipSequence` plays a queue of clips one after another.
+///
+/// A clip moves on to the next in the queue when it finishes. The time left over from the update
+/// which finished it is played by the next clip, so no time is lost between clips however large
+/// the updates are. A `Loop` clip finishes at the end of its current cycle once another clip is
+/// queued behind it; until then, it loops as usual.
+///
+/// The last clip is never removed, so it stays active (and its final frame shown, if it's a
+/// `Hold` clip) until another is queued.
+#[derive(Debug, Clone, Default)]
+pub struct ClipSequence {
+    clips: VecDeque<AnimationClip>,
+}
+
+impl ClipSequence {
+    pub fn new() -> Self {
+        ClipSequence { clips: VecDeque::new() }
+    }
+
+    /// Queues `clip` to play after the clips already in the sequence.
+    pub fn push(&mut self, clip: AnimationClip) {
+        self.clips.push_back(clip);
+    }
+
+    /// Queues a new clip for the tag `key` in `store`.
+    ///
+    /// Returns `false` (queueing nothing) if the store has no clip named `key`.
+    pub fn push_from(&mut self, store: &ClipStore, key: &str, mode: PlayMode) -> bool {
+        match store.create(key, mode) {
+            Some(clip) => {
+                self.push(clip);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes every clip, including the active one.
+    pub fn clear(&mut self) {
+        self.clips.clear();
+    }
+
+    /// The clip being played, if any.
+    pub fn active(&self) -> Option<&AnimationClip> {
+        self.clips.front()
+    }
+
+    pub fn active_mut(&mut self) -> Option<&mut AnimationClip> {
+        self.clips.front_mut()
+    }
+
+    /// The number of clips in the sequence, including the active one.
+    pub fn len(&self) -> usize {
+        self.clips.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clips.is_empty()
+    }
+
+    pub fn get_cell(&self) -> Option<usize> {
+        self.active().and_then(|clip| clip.get_cell())
+    }
+
+    /// Advances the sequence by `dt`, moving on to later clips as earlier ones finish. Returns
+    /// the events reported by each clip played, in order.
+    ///
+    /// The first cell of each clip moved on to is reported as entered, just as the first update
+    /// of a lone `AnimationClip` reports the cell it starts on.
+    pub fn update(&mut self, dt: Delta) -> Vec<ClipEvent> {
+        let mut events = vec![];
+        let mut dt = dt;
+        while self.clips.len() > 1 {
+            match self.clips[0].finish(dt) {
+                Some((finishing, remaining)) => {
+                    events.extend(finishing);
+                    self.clips.pop_front();
+                    dt = remaining;
+                    events.extend(self.clips[0].entered());
+                }
+                None => break,
+            }
+        }
+        if let Some(clip) = self.clips.front_mut() {
+            events.extend(clip.update(dt));
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sprites::test::{get_pitcher_sheet, get_two_sheet};
+
+    #[test]
+    fn test_overflow_carries_to_next_clip() {
+        let store = ClipStore::new(&get_pitcher_sheet());
+        let mut sequence = ClipSequence::new();
+        assert!(sequence.push_from(&store, "Pitching", PlayMode::OneShot));
+        assert!(sequence.push_from(&store, "Ready", PlayMode::Loop));
+        assert!(!sequence.push_from(&store, "Throwing", PlayMode::Loop));
+
+        // Pitching lasts 400ms
+        let events = sequence.update(Delta::from_millis(450.));
+        assert_eq!(sequence.active().unwrap().name, "Ready");
+        assert_eq!(sequence.len(), 1);
+        assert_eq!(sequence.active().unwrap().current_time, Delta::from_millis(50.));
+        assert_eq!(
+            events,
+            vec![
+                ClipEvent::FrameEntered(14),
+                ClipEvent::FrameEntered(15),
+                ClipEvent::FrameEntered(16),
+                ClipEvent::FrameEntered(17),
+                ClipEvent::Finished,
+                ClipEvent::FrameEntered(0),
+            ]
+        );
+
+        sequence.update(Delta::from_millis(100.));
+        assert_eq!(sequence.get_cell(), Some(1));
+    }
+
+    #[test]
+    fn test_several_clips_in_one_update() {
+        let store = ClipStore::new(&get_two_sheet());
+        let mut sequence = ClipSequence::new();
+        sequence.push_from(&store, "Alpha", PlayMode::OneShot);
+        sequence.push_from(&store, "Alpha", PlayMode::Hold);
+        sequence.push_from(&store, "Alpha", PlayMode::Loop);
+
+        // each clip lasts 30ms
+        sequence.update(Delta::from_millis(75.));
+        assert_eq!(sequence.len(), 1);
+        assert_eq!(sequence.active().unwrap().current_time, Delta::from_millis(15.));
+        assert_eq!(sequence.get_cell(), Some(1));
+    }
+
+    #[test]
+    fn test_loop_finishes_its_cycle_when_followed() {
+        let store = ClipStore::new(&get_pitcher_sheet());
+        let mut sequence = ClipSequence::new();
+        sequence.push_from(&store, "Ready", PlayMode::Loop);
+
+        // Ready lasts 800ms, and keeps looping while it's the last clip
+        sequence.update(Delta::from_millis(1550.));
+        assert_eq!(sequence.active().unwrap().name, "Ready");
+
+        sequence.push_from(&store, "Pitching", PlayMode::OneShot);
+        let events = sequence.update(Delta::from_millis(100.));
+        assert_eq!(sequence.active().unwrap().name, "Pitching");
+        assert_eq!(sequence.get_cell(), Some(14));
+        assert_eq!(
+            events,
+            vec![ClipEvent::LoopCompleted, ClipEvent::FrameEntered(14)]
+        );
+    }
+
+    #[test]
+    fn test_last_clip_stays_active() {
+        let store = ClipStore::new(&get_two_sheet());
+        let mut sequence = ClipSequence::new();
+        sequence.push_from(&store, "Alpha", PlayMode::OneShot);
+
+        sequence.update(Delta::from_millis(40.));
+        assert_eq!(sequence.active().unwrap().name, "Alpha");
+        assert_eq!(sequence.get_cell(), None);
+
+        // a finished clip moves on as soon as there's something to play
+        sequence.push_from(&store, "Alpha", PlayMode::Loop);
+        sequence.update(Delta::from_millis(5.));
+        assert_eq!(sequence.len(), 1);
+        assert_eq!(sequence.active().unwrap().current_time, Delta::from_millis(5.));
+        assert_eq!(sequence.get_cell(), Some(0));
+    }
+}
@@ -1,48 +1,393 @@
-use specs::{Join, ReadStorage, System};
+use specs::{Entities, Fetch, FetchMut, Join, ReadStorage, System, WriteStorage};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::hash::Hash;
 use std::sync::mpsc::Sender;
+use ggez::graphics::Color;
+use assets::ImageHandle;
 use components;
+use resources::{AnimationEvents, Camera, Time};
+use components::Origin;
+use sprites::Region;
 
+/// `DrawCommand`s are sent by `Renderer` for each frame, then executed on the main thread by a
+/// `render::Executor`.
 pub enum DrawCommand {
     DrawTransformed {
-        path: String,
+        image: ImageHandle,
         frame: u32,
+        /// The area of the image to draw, in pixels. `None` draws the whole image.
+        src: Option<Region>,
+        /// The point of the drawn area placed at `x`, `y`.
+        origin: Origin,
+        color: Color,
         x: f32,
         y: f32,
         rot: f32,
         sx: f32,
         sy: f32,
     },
+    /// Marks the end of a frame's worth of commands.
     Flush,
 }
 
+/// The radius, in unscaled pixels, assumed for sprites whose size the `Renderer` can't know.
+pub const DEFAULT_CULL_RADIUS: f32 = 256.;
+
+/// `Renderer` sends a `DrawCommand` for each entity with a `Body` and `Sprited`, sorted by
+/// `Sprited.layer` then `Sprited.depth`, followed by a `DrawCommand::Flush`. Fully transparent
+/// sprites are skipped.
+///
+/// Positions are mapped to the screen by the world's `resources::Camera`, and sprites outside its
+/// viewport are skipped.
 #[derive(Clone)]
 pub struct Renderer {
     pub tx: Sender<DrawCommand>,
+    /// When culling, sprites drawn without a source region (so whose size isn't known) are
+    /// treated as circles of this radius around their origin, before scaling.
+    pub cull_radius: f32,
+    /// When set, sprites within a layer are ordered by their y position before their depth, so
+    /// sprites lower on the screen are drawn over those above them (as is common in top-down
+    /// games).
+    pub y_sort: bool,
+}
+
+impl Renderer {
+    pub fn new(tx: Sender<DrawCommand>) -> Self {
+        Renderer {
+            tx: tx,
+            cull_radius: DEFAULT_CULL_RADIUS,
+            y_sort: false,
+        }
+    }
+}
+
+/// The values draw commands are ordered by.
+#[derive(Debug, Clone, PartialEq)]
+struct DrawOrder {
+    layer: i32,
+    depth: f32,
+    y: f32,
+}
+
+impl DrawOrder {
+    fn cmp(&self, other: &DrawOrder, y_sort: bool) -> Ordering {
+        let by_y = if y_sort {
+            self.y.partial_cmp(&other.y).unwrap_or(Ordering::Equal)
+        } else {
+            Ordering::Equal
+        };
+        self.layer.cmp(&other.layer).then(by_y).then(
+            self.depth
+                .partial_cmp(&other.depth)
+                .unwrap_or(Ordering::Equal),
+        )
+    }
 }
 
 
 impl<'a> System<'a> for Renderer {
     type SystemData = (
+        Entities<'a>,
+        Fetch<'a, Camera>,
         ReadStorage<'a, components::Body>,
+        ReadStorage<'a, components::GlobalTransform>,
         ReadStorage<'a, components::Sprited>,
+        ReadStorage<'a, components::Animated>,
     );
     fn run(&mut self, data: Self::SystemData) {
 
-        let (body, sprited) = data;
+        let (entities, camera, body, global, sprited, animated) = data;
+        let mut commands = vec![];
         // update entities
-        for (b, s) in (&body, &sprited).join() {
-            let frame_id = 0;
-            self.tx
-                .send(DrawCommand::DrawTransformed {
-                    path: s.path.to_string(),
+        for (e, b, s) in (&*entities, &body, &sprited).join() {
+            let (frame_id, src, pivot) = match animated.get(e) {
+                Some(a) => match a.clip.get_cell() {
+                    Some(idx) => (
+                        idx as u32,
+                        a.sheet.frames.get(idx).map(|f| f.bbox.clone()),
+                        a.sheet.pivot(idx),
+                    ),
+                    // a one-shot clip which has finished has nothing to show
+                    None => continue,
+                },
+                None => (0, None, None),
+            };
+            let origin = match pivot {
+                Some((x, y)) => Origin::Absolute(x as f32, y as f32),
+                None => b.origin,
+            };
+            // entities which haven't been through `TransformPropagation` are treated as roots
+            let t = match global.get(e) {
+                Some(t) => t.clone(),
+                None => components::GlobalTransform::from(b),
+            };
+            let (x, y) = camera.world_to_screen(t.x, t.y);
+            let (sx, sy) = (t.scale_x * camera.zoom, t.scale_y * camera.zoom);
+            let color = s.color();
+            if color.a <= 0. {
+                continue;
+            }
+
+            // the origin may be anywhere in the frame, so allow for the whole diagonal
+            let radius = match src {
+                Some(ref r) => (r.width as f32).hypot(r.height as f32),
+                None => self.cull_radius,
+            };
+            if !camera.is_visible(x, y, radius * sx.abs().max(sy.abs())) {
+                continue;
+            }
+
+            let order = DrawOrder {
+                layer: s.layer,
+                depth: s.depth,
+                y: t.y,
+            };
+            commands.push((
+                order,
+                DrawCommand::DrawTransformed {
+                    image: s.image,
                     frame: frame_id,
-                    x: b.x,
-                    y: b.y,
-                    rot: b.rotation,
-                    sx: b.scale_x,
-                    sy: b.scale_y,
-                })
-                .unwrap();
+                    src: src,
+                    origin: origin,
+                    color: color,
+                    x: x,
+                    y: y,
+                    rot: t.rotation - camera.rotation,
+                    sx: sx,
+                    sy: sy,
+                },
+            ));
+        }
+
+        // the sort is stable, so entities which compare equal keep their storage order
+        let y_sort = self.y_sort;
+        commands.sort_by(|a, b| a.0.cmp(&b.0, y_sort));
+        for (_, cmd) in commands {
+            self.tx.send(cmd).unwrap();
+        }
+        self.tx.send(DrawCommand::Flush).unwrap();
+    }
+}
+
+/// `TransformPropagation` computes the `GlobalTransform` of each entity with a `Body`, composing
+/// it with the bodies of its `Parent`s. It should run after anything which moves bodies, and
+/// before `Renderer`.
+///
+/// An entity whose parent has no `Body` (or has been deleted) is treated as a root. Cycles are
+/// broken arbitrarily, so avoid them.
+#[derive(Clone)]
+pub struct TransformPropagation;
+
+impl<'a> System<'a> for TransformPropagation {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, components::Parent>,
+        ReadStorage<'a, components::Body>,
+        WriteStorage<'a, components::GlobalTransform>,
+    );
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, parent, body, mut global) = data;
+        let mut computed = HashMap::new();
+        for (e, _) in (&*entities, &body).join() {
+            let t = global_transform(
+                e,
+                |e| parent.get(e).map(|p| p.entity),
+                |e| body.get(e),
+                &mut computed,
+            );
+            if let Some(t) = t {
+                global.insert(e, t);
+            }
+        }
+    }
+}
+
+/// Finds the global transform of `entity` by walking up its parents, caching the transform of
+/// each entity passed through in `computed` so siblings don't repeat the walk.
+fn global_transform<'b, K, P, B>(
+    entity: K,
+    parent_of: P,
+    body_of: B,
+    computed: &mut HashMap<K, components::GlobalTransform>,
+) -> Option<components::GlobalTransform>
+where
+    K: Copy + Eq + Hash,
+    P: Fn(K) -> Option<K>,
+    B: Fn(K) -> Option<&'b components::Body>,
+{
+    let mut chain = vec![];
+    let mut current = entity;
+    let mut base = components::GlobalTransform::identity();
+    loop {
+        if let Some(t) = computed.get(&current) {
+            base = t.clone();
+            break;
+        }
+        if chain.iter().any(|&(e, _)| e == current) {
+            break;
+        }
+        let b = match body_of(current) {
+            Some(b) => b,
+            None => break,
+        };
+        chain.push((current, b));
+        match parent_of(current) {
+            Some(p) => current = p,
+            None => break,
         }
     }
+    for (e, b) in chain.into_iter().rev() {
+        base = base.compose(b);
+        computed.insert(e, base.clone());
+    }
+    computed.get(&entity).cloned()
+}
+
+/// `Animator` advances the clip of each `Animated` entity by `Time.delta`, so animations follow
+/// `Time.time_scale` along with the rest of the game. Each clip's own `rate` applies on top.
+///
+/// The events produced by the clips are added to the `AnimationEvents` resource, which keeps them
+/// until they're drained.
+#[derive(Clone)]
+pub struct Animator;
+
+impl<'a> System<'a> for Animator {
+    type SystemData = (
+        Entities<'a>,
+        Fetch<'a, Time>,
+        FetchMut<'a, AnimationEvents>,
+        WriteStorage<'a, components::Animated>,
+    );
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, time, mut events, mut animated) = data;
+        for (e, a) in (&*entities, &mut animated).join() {
+            let clip_events = a.clip.update(time.delta);
+            events.0.extend(clip_events.into_iter().map(|x| (e, x)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use components::{Body, GlobalTransform};
+    use std::f32::consts::FRAC_PI_2;
+
+    fn body(x: f32, y: f32, scale: f32, rotation: f32) -> Body {
+        Body {
+            scale_x: scale,
+            scale_y: scale,
+            rotation: rotation,
+            ..Body::new(x, y)
+        }
+    }
+
+    fn assert_near(actual: &GlobalTransform, expected: &GlobalTransform) {
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-4;
+        assert!(
+            close(actual.x, expected.x) && close(actual.y, expected.y) &&
+                close(actual.scale_x, expected.scale_x) &&
+                close(actual.scale_y, expected.scale_y) &&
+                close(actual.rotation, expected.rotation),
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_global_transform_composes_parents() {
+        // 0 <- 1 <- 2, with 3 a root
+        let bodies = vec![
+            body(100., 50., 2., FRAC_PI_2),
+            body(10., 0., 1., 0.),
+            body(0., 5., 0.5, 0.),
+            body(7., 7., 1., 0.),
+        ];
+        let parents = vec![None, Some(0), Some(1), None];
+        let mut computed = HashMap::new();
+        let find = |e: usize, computed: &mut HashMap<usize, GlobalTransform>| {
+            global_transform(e, |e| parents[e], |e| bodies.get(e), computed).unwrap()
+        };
+
+        // scaled by 2, then rotated a quarter turn
+        assert_near(
+            &find(2, &mut computed),
+            &GlobalTransform {
+                x: 90.,
+                y: 70.,
+                scale_x: 1.,
+                scale_y: 1.,
+                rotation: FRAC_PI_2,
+            },
+        );
+        assert_near(
+            computed.get(&1).unwrap(),
+            &GlobalTransform {
+                x: 100.,
+                y: 70.,
+                scale_x: 2.,
+                scale_y: 2.,
+                rotation: FRAC_PI_2,
+            },
+        );
+        assert_near(&find(3, &mut computed), &GlobalTransform::from(&bodies[3]));
+    }
+
+    #[test]
+    fn test_global_transform_survives_cycles() {
+        let bodies = vec![body(1., 0., 1., 0.), body(0., 1., 1., 0.)];
+        let parents = vec![Some(1), Some(0)];
+        let mut computed = HashMap::new();
+        let t = global_transform(0, |e| parents[e], |e| bodies.get(e), &mut computed);
+        assert_near(
+            &t.unwrap(),
+            &GlobalTransform {
+                x: 1.,
+                y: 1.,
+                scale_x: 1.,
+                scale_y: 1.,
+                rotation: 0.,
+            },
+        );
+    }
+
+    fn order(layer: i32, depth: f32, y: f32) -> DrawOrder {
+        DrawOrder {
+            layer: layer,
+            depth: depth,
+            y: y,
+        }
+    }
+
+    #[test]
+    fn test_layer_then_depth() {
+        let mut items = vec![order(1, 0., 0.), order(0, 5., 0.), order(0, -1., 0.)];
+        items.sort_by(|a, b| a.cmp(b, false));
+        assert_eq!(
+            items,
+            vec![order(0, -1., 0.), order(0, 5., 0.), order(1, 0., 0.)]
+        );
+    }
+
+    #[test]
+    fn test_y_sort_within_layer() {
+        let mut items = vec![
+            order(1, 0., 10.),
+            order(0, 1., 50.),
+            order(0, 9., 20.),
+            order(0, 0., 50.),
+        ];
+        items.sort_by(|a, b| a.cmp(b, true));
+        assert_eq!(
+            items,
+            vec![
+                order(0, 9., 20.),
+                order(0, 0., 50.),
+                order(0, 1., 50.),
+                order(1, 0., 10.),
+            ]
+        );
+    }
 }
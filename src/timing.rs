@@ -0,0 +1,151 @@
+//! The `timing` module turns the frame times reported by ggez into the fixed size steps a specs
+//! world is updated in.
+//!
+//! Running the simulation with a fixed timestep keeps it deterministic and stable whatever the
+//! frame rate. Rendering still happens once per frame, so `Time.alpha` says how far the frame is
+//! between two steps, for smoothing motion.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! extern crate ggez;
+//! extern crate specs;
+//! extern crate omn_labs;
+//!
+//! use std::time::Duration;
+//! use ggez::Context;
+//! use omn_labs::resources::Time;
+//! use omn_labs::timing::GameLoop;
+//!
+//! struct Game<'a, 'b> {
+//!     world: specs::World,
+//!     dispatcher: specs::Dispatcher<'a, 'b>,
+//!     game_loop: GameLoop,
+//! }
+//!
+//! impl<'a, 'b> Game<'a, 'b> {
+//!     // Called from `ggez::event::EventHandler::update`.
+//!     fn update(&mut self, _ctx: &mut Context, dt: Duration) {
+//!         // Runs the dispatcher 0 or more times, 1/60th of a second of game time each.
+//!         self.game_loop.update(&mut self.world, &mut self.dispatcher, dt);
+//!         println!("alpha: {}", self.world.read_resource::<Time>().alpha);
+//!     }
+//! }
+//! # fn main() {}
+//! ```
+
+use std::time::Duration;
+use specs::{Dispatcher, World};
+use resources::Time;
+use Delta;
+
+/// The number of steps per second run by `GameLoop::default()`.
+pub const DEFAULT_STEP_RATE: f32 = 60.;
+
+/// The most steps run for a single frame by default. Without a limit, a slow frame leads to more
+/// steps next frame, which makes that frame slower still.
+pub const DEFAULT_MAX_STEPS: u32 = 5;
+
+/// `FixedTimestep` accumulates frame times and tells you how many fixed size steps to run.
+#[derive(Clone, Debug)]
+pub struct FixedTimestep {
+    pub step: Delta,
+    /// Time beyond this many steps in a single frame is dropped, slowing the game down rather
+    /// than letting it fall ever further behind.
+    pub max_steps: u32,
+    accumulator: Delta,
+}
+
+impl FixedTimestep {
+    pub fn new(step: Delta) -> Self {
+        FixedTimestep {
+            step: step,
+            max_steps: DEFAULT_MAX_STEPS,
+            accumulator: Delta::zero(),
+        }
+    }
+
+    /// Adds `dt`, then returns the number of whole steps now due.
+    pub fn advance(&mut self, dt: Delta) -> u32 {
+        self.accumulator += dt;
+        let mut steps = 0;
+        while self.accumulator >= self.step {
+            if steps == self.max_steps {
+                self.accumulator = self.accumulator % self.step;
+                break;
+            }
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+        steps
+    }
+
+    /// How much of the next step has accumulated, from 0 to 1.
+    pub fn alpha(&self) -> f32 {
+        self.accumulator / self.step
+    }
+}
+
+/// `GameLoop` runs a specs `Dispatcher` at a fixed timestep, keeping the world's
+/// `resources::Time` up to date.
+#[derive(Clone, Debug)]
+pub struct GameLoop {
+    pub timestep: FixedTimestep,
+}
+
+impl Default for GameLoop {
+    fn default() -> Self {
+        GameLoop::new(Delta::from_secs(1. / DEFAULT_STEP_RATE))
+    }
+}
+
+impl GameLoop {
+    /// A loop updating the world every `step` of game time.
+    pub fn new(step: Delta) -> Self {
+        GameLoop { timestep: FixedTimestep::new(step) }
+    }
+
+    /// Advances game time by `dt` of real time (scaled by `Time.time_scale`), dispatching once
+    /// for each step due, and returns the number of steps run.
+    ///
+    /// `world` must have a `Time` resource, which can start out as `Time::default()`.
+    pub fn update(&mut self, world: &mut World, dispatcher: &mut Dispatcher, dt: Duration) -> u32 {
+        let time_scale = world.read_resource::<Time>().time_scale;
+        let steps = self.timestep.advance(Delta::from(dt) * time_scale);
+        for _ in 0..steps {
+            {
+                let mut time = world.write_resource::<Time>();
+                time.delta = self.timestep.step;
+                time.elapsed += self.timestep.step.to_duration();
+                time.frame_count += 1;
+            }
+            dispatcher.dispatch(&mut world.res);
+        }
+        world.write_resource::<Time>().alpha = self.timestep.alpha();
+        steps
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_steps_carry_remainder() {
+        let mut timestep = FixedTimestep::new(Delta::from_secs(0.25));
+        assert_eq!(timestep.advance(Delta::from_secs(0.125)), 0);
+        assert_eq!(timestep.alpha(), 0.5);
+        assert_eq!(timestep.advance(Delta::from_secs(0.625)), 3);
+        assert_eq!(timestep.alpha(), 0.);
+    }
+
+    #[test]
+    fn test_steps_are_capped() {
+        let mut timestep = FixedTimestep::new(Delta::from_secs(0.25));
+        timestep.max_steps = 2;
+        assert_eq!(timestep.advance(Delta::from_secs(1.125)), 2);
+        // the excess is dropped, but the partial step is kept
+        assert_eq!(timestep.alpha(), 0.5);
+        assert_eq!(timestep.advance(Delta::from_secs(0.)), 0);
+    }
+}